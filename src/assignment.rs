@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+
+#[derive(Debug)]
+pub enum AssignmentError<K, V> {
+    /// No way to give every key its own value from its candidates.
+    NoAssignment,
+    /// Two different assignments satisfy the candidates.
+    Ambiguous(HashMap<K, V>, HashMap<K, V>),
+}
+
+impl<K: Eq + Hash, V: PartialEq> PartialEq for AssignmentError<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (AssignmentError::NoAssignment, AssignmentError::NoAssignment) => true,
+            (AssignmentError::Ambiguous(a1, a2), AssignmentError::Ambiguous(b1, b2)) => {
+                a1 == b1 && a2 == b2
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Assigns every key a distinct value from its candidate set, when there is exactly one way to
+/// do so.
+///
+/// Keys with a single candidate are fixed first and their value removed from the other keys.
+/// That alone resolves every candidate set with a unique solution; when it gets stuck,
+/// backtracking up to the second solution tells a missing assignment from an ambiguous one.
+///
+/// # Errors
+/// `NoAssignment` when the candidates can't be satisfied and `Ambiguous` with two of the
+/// solutions when they don't determine a single one.
+///
+/// # Panics
+pub fn unique_assignment<K, V, S, T>(
+    candidates: &HashMap<K, HashSet<V, T>, S>,
+) -> Result<HashMap<K, V>, AssignmentError<K, V>>
+where
+    K: Eq + Hash + Clone + Ord,
+    V: Eq + Hash + Clone + Ord,
+    S: BuildHasher,
+    T: BuildHasher,
+{
+    // Sorted ids keep the search, and so the reported solutions, deterministic
+    let mut keys: Vec<&K> = candidates.keys().collect();
+    keys.sort();
+    let mut values: Vec<&V> = candidates.values().flatten().collect();
+    values.sort();
+    values.dedup();
+    let mut domains: Vec<Vec<usize>> = keys
+        .iter()
+        .map(|key| {
+            let mut domain: Vec<usize> = candidates[*key]
+                .iter()
+                .map(|value| values.binary_search(&value).unwrap())
+                .collect();
+            domain.sort_unstable();
+            domain
+        })
+        .collect();
+
+    if !eliminate(&mut domains) {
+        return Err(AssignmentError::NoAssignment);
+    }
+    let mut solutions = Vec::new();
+    search(
+        &domains,
+        &mut vec![None; keys.len()],
+        &mut vec![false; values.len()],
+        &mut solutions,
+    );
+
+    let to_map = |solution: &Vec<usize>| -> HashMap<K, V> {
+        keys.iter()
+            .zip(solution)
+            .map(|(key, value)| ((*key).clone(), values[*value].clone()))
+            .collect()
+    };
+    match solutions.as_slice() {
+        [] => Err(AssignmentError::NoAssignment),
+        [solution] => Ok(to_map(solution)),
+        [first, second, ..] => Err(AssignmentError::Ambiguous(to_map(first), to_map(second))),
+    }
+}
+
+/// Removes values of single-candidate keys from all other keys until nothing changes. Returns
+/// `false` when some key is left without candidates.
+fn eliminate(domains: &mut [Vec<usize>]) -> bool {
+    let mut fixed = vec![false; domains.len()];
+    while let Some(key) = (0..domains.len()).find(|key| !fixed[*key] && domains[*key].len() == 1) {
+        fixed[key] = true;
+        let value = domains[key][0];
+        for (other, domain) in domains.iter_mut().enumerate() {
+            if other != key {
+                domain.retain(|v| *v != value);
+            }
+        }
+    }
+    domains.iter().all(|domain| !domain.is_empty())
+}
+
+fn search(
+    domains: &[Vec<usize>],
+    assigned: &mut Vec<Option<usize>>,
+    used: &mut Vec<bool>,
+    solutions: &mut Vec<Vec<usize>>,
+) {
+    if solutions.len() >= 2 {
+        return;
+    }
+    // Branch on the open key with the fewest free values
+    let open = (0..domains.len())
+        .filter(|key| assigned[*key].is_none())
+        .min_by_key(|key| domains[*key].iter().filter(|v| !used[**v]).count());
+    let Some(key) = open else {
+        solutions.push(assigned.iter().map(|value| value.unwrap()).collect());
+        return;
+    };
+    for &value in &domains[key] {
+        if used[value] {
+            continue;
+        }
+        assigned[key] = Some(value);
+        used[value] = true;
+        search(domains, assigned, used, solutions);
+        used[value] = false;
+        assigned[key] = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn candidates(input: &[(&'static str, &[u32])]) -> HashMap<&'static str, HashSet<u32>> {
+        input
+            .iter()
+            .map(|(key, values)| (*key, values.iter().copied().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn solved_by_elimination() {
+        let input = candidates(&[("a", &[1, 2, 3]), ("b", &[2]), ("c", &[2, 3])]);
+        let expected = HashMap::from([("a", 1), ("b", 2), ("c", 3)]);
+        assert_eq!(Ok(expected), unique_assignment(&input));
+    }
+
+    #[test]
+    fn search_after_elimination_stalls() {
+        // No key has a single candidate, so only the search can tell these apart
+        let input = candidates(&[
+            ("a", &[1, 2]),
+            ("b", &[1, 2, 3]),
+            ("c", &[3, 4]),
+            ("d", &[4, 1]),
+        ]);
+        assert!(matches!(
+            unique_assignment(&input),
+            Err(AssignmentError::Ambiguous(_, _))
+        ));
+
+        let input = candidates(&[
+            ("a", &[1, 2]),
+            ("b", &[1, 2]),
+            ("c", &[1, 2, 3]),
+            ("d", &[1, 3]),
+        ]);
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            unique_assignment(&input)
+        );
+    }
+
+    #[test]
+    fn reports_ambiguity() {
+        let input = candidates(&[("a", &[1, 2]), ("b", &[1, 2])]);
+        assert_eq!(
+            Err(AssignmentError::Ambiguous(
+                HashMap::from([("a", 1), ("b", 2)]),
+                HashMap::from([("a", 2), ("b", 1)])
+            )),
+            unique_assignment(&input)
+        );
+    }
+
+    #[test]
+    fn reports_missing_assignment() {
+        let input = candidates(&[("a", &[1]), ("b", &[1]), ("c", &[1, 2])]);
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            unique_assignment(&input)
+        );
+        let input = candidates(&[("a", &[1, 2]), ("b", &[1, 2]), ("c", &[1, 2])]);
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            unique_assignment(&input)
+        );
+        let input = candidates(&[("a", &[])]);
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            unique_assignment(&input)
+        );
+        let input = candidates(&[]);
+        assert_eq!(Ok(HashMap::new()), unique_assignment(&input));
+    }
+}
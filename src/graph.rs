@@ -0,0 +1,314 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Directed graph whose vertices are interned labels. Vertex ids are indices `0..len()` in the
+/// order the labels were first added.
+#[derive(Debug, Clone)]
+pub struct Graph<V> {
+    labels: Vec<V>,
+    ids: HashMap<V, usize>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl<V: Eq + Hash + Clone> Default for Graph<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Eq + Hash + Clone> Graph<V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Graph {
+            labels: Vec::new(),
+            ids: HashMap::new(),
+            successors: Vec::new(),
+        }
+    }
+
+    /// Graph with the edges `from -> to` of all pairs.
+    #[must_use]
+    pub fn from_edges(edges: impl IntoIterator<Item = (V, V)>) -> Self {
+        let mut graph = Graph::new();
+        for (from, to) in edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    /// Id of the vertex, which is created when the label is new.
+    pub fn add_vertex(&mut self, label: V) -> usize {
+        if let Some(id) = self.ids.get(&label) {
+            return *id;
+        }
+        let id = self.labels.len();
+        self.ids.insert(label.clone(), id);
+        self.labels.push(label);
+        self.successors.push(Vec::new());
+        id
+    }
+
+    pub fn add_edge(&mut self, from: V, to: V) {
+        let from = self.add_vertex(from);
+        let to = self.add_vertex(to);
+        self.successors[from].push(to);
+    }
+
+    #[must_use]
+    pub fn id(&self, label: &V) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    #[must_use]
+    pub fn label(&self, id: usize) -> &V {
+        &self.labels[id]
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    #[must_use]
+    pub fn successors(&self, id: usize) -> &[usize] {
+        &self.successors[id]
+    }
+
+    #[must_use]
+    pub fn in_degrees(&self) -> Vec<usize> {
+        let mut result = vec![0; self.len()];
+        for to in self.successors.iter().flatten() {
+            result[*to] += 1;
+        }
+        result
+    }
+
+    /// Kahn's algorithm. Among the vertices ready at the same time, the one with the smallest
+    /// `key` goes first.
+    ///
+    /// # Errors
+    /// Returns a cycle, see [`Graph::find_cycle`], when the graph has one.
+    pub fn topological_sort_by_key<K: Ord>(
+        &self,
+        mut key: impl FnMut(&V) -> K,
+    ) -> Result<Vec<usize>, Vec<usize>> {
+        let mut in_degrees = self.in_degrees();
+        let mut ready: BinaryHeap<Reverse<(K, usize)>> = in_degrees
+            .iter()
+            .enumerate()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| Reverse((key(&self.labels[id]), id)))
+            .collect();
+        let mut result = Vec::with_capacity(self.len());
+        while let Some(Reverse((_, id))) = ready.pop() {
+            result.push(id);
+            for next in &self.successors[id] {
+                in_degrees[*next] -= 1;
+                if in_degrees[*next] == 0 {
+                    ready.push(Reverse((key(&self.labels[*next]), *next)));
+                }
+            }
+        }
+        if result.len() == self.len() {
+            Ok(result)
+        } else {
+            Err(self.find_cycle().unwrap_or_default())
+        }
+    }
+
+    /// Vertices of some cycle in order, each one with an edge to the next and the last one with
+    /// an edge to the first.
+    #[must_use]
+    pub fn find_cycle(&self) -> Option<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            /// Position on the current path
+            OnPath(usize),
+            Done,
+        }
+
+        let mut state = vec![State::New; self.len()];
+        for root in 0..self.len() {
+            if state[root] != State::New {
+                continue;
+            }
+            state[root] = State::OnPath(0);
+            // Path of vertices together with the index of their next successor to visit
+            let mut path = vec![(root, 0)];
+            while let Some((id, next_index)) = path.last_mut() {
+                let Some(&next) = self.successors[*id].get(*next_index) else {
+                    state[*id] = State::Done;
+                    path.pop();
+                    continue;
+                };
+                *next_index += 1;
+                match state[next] {
+                    State::New => {
+                        state[next] = State::OnPath(path.len());
+                        path.push((next, 0));
+                    }
+                    State::OnPath(begin) => {
+                        return Some(path[begin..].iter().map(|(id, _)| *id).collect());
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Number of edges on the shortest path from `start` to every vertex, `None` when unreachable.
+    #[must_use]
+    pub fn bfs_distances(&self, start: usize) -> Vec<Option<usize>> {
+        let mut result = vec![None; self.len()];
+        result[start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            let distance = result[id].unwrap_or_default() + 1;
+            for next in &self.successors[id] {
+                if result[*next].is_none() {
+                    result[*next] = Some(distance);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        result
+    }
+
+    /// Vertices reachable from `start` in depth-first preorder, successors visited in the order
+    /// their edges were added.
+    #[must_use]
+    pub fn dfs_order(&self, start: usize) -> Vec<usize> {
+        let mut visited = vec![false; self.len()];
+        let mut result = Vec::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if visited[id] {
+                continue;
+            }
+            visited[id] = true;
+            result.push(id);
+            stack.extend(
+                self.successors[id]
+                    .iter()
+                    .rev()
+                    .filter(|next| !visited[**next]),
+            );
+        }
+        result
+    }
+
+    /// Vertices of a path from `from` to `to` with the fewest edges, both ends included.
+    #[must_use]
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut previous: Vec<Option<usize>> = vec![None; self.len()];
+        let mut visited = vec![false; self.len()];
+        visited[from] = true;
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(p) = previous[current] {
+                    path.push(p);
+                    current = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in &self.successors[id] {
+                if !visited[*next] {
+                    visited[*next] = true;
+                    previous[*next] = Some(id);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn graph(edges: &[(&'static str, &'static str)]) -> Graph<&'static str> {
+        Graph::from_edges(edges.iter().copied())
+    }
+
+    fn labels(graph: &Graph<&'static str>, ids: &[usize]) -> Vec<&'static str> {
+        ids.iter().map(|id| *graph.label(*id)).collect()
+    }
+
+    #[test]
+    fn interning() {
+        let mut g = graph(&[("a", "b"), ("b", "c"), ("a", "c")]);
+        assert_eq!(3, g.len());
+        assert_eq!(Some(1), g.id(&"b"));
+        assert_eq!(None, g.id(&"d"));
+        assert_eq!(1, g.add_vertex("b"));
+        assert_eq!(3, g.add_vertex("d"));
+        assert_eq!(&[1, 2], g.successors(0));
+        assert_eq!(vec![0, 1, 2, 0], g.in_degrees());
+        assert!(Graph::<u8>::new().is_empty());
+    }
+
+    #[test]
+    fn topological_sort_tie_breaking() {
+        let g = graph(&[
+            ("c", "a"),
+            ("c", "f"),
+            ("a", "b"),
+            ("a", "d"),
+            ("b", "e"),
+            ("d", "e"),
+            ("f", "e"),
+        ]);
+        let order = g.topological_sort_by_key(|label| *label).unwrap();
+        assert_eq!(vec!["c", "a", "b", "d", "f", "e"], labels(&g, &order));
+        let order = g.topological_sort_by_key(|label| Reverse(*label)).unwrap();
+        assert_eq!(vec!["c", "f", "a", "d", "b", "e"], labels(&g, &order));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let g = graph(&[("a", "b"), ("b", "c"), ("c", "d"), ("d", "b"), ("x", "a")]);
+        assert_eq!(Some(vec![1, 2, 3]), g.find_cycle());
+        let cycle = g.topological_sort_by_key(|label| *label).unwrap_err();
+        assert_eq!(vec!["b", "c", "d"], labels(&g, &cycle));
+
+        let g = graph(&[("a", "a")]);
+        assert_eq!(Some(vec![0]), g.find_cycle());
+        let g = graph(&[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+        assert_eq!(None, g.find_cycle());
+    }
+
+    #[test]
+    fn traversals() {
+        let g = graph(&[
+            ("a", "b"),
+            ("a", "c"),
+            ("b", "d"),
+            ("c", "d"),
+            ("d", "e"),
+            ("f", "a"),
+        ]);
+        assert_eq!(
+            vec![Some(0), Some(1), Some(1), Some(2), Some(3), None],
+            g.bfs_distances(0)
+        );
+        assert_eq!(vec!["a", "b", "d", "e", "c"], labels(&g, &g.dfs_order(0)));
+        let path = g.shortest_path(5, 4).unwrap();
+        assert_eq!(vec!["f", "a", "b", "d", "e"], labels(&g, &path));
+        assert_eq!(Some(vec![2]), g.shortest_path(2, 2));
+        assert_eq!(None, g.shortest_path(4, 0));
+    }
+}
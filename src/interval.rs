@@ -0,0 +1,328 @@
+use num::PrimInt;
+
+/// Inclusive range of integers `from..=to`, never empty.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Interval<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /// `None` when `from > to`.
+    #[must_use]
+    pub fn new(from: T, to: T) -> Option<Self> {
+        (from <= to).then_some(Interval { from, to })
+    }
+
+    #[must_use]
+    pub fn contains(&self, value: T) -> bool {
+        self.from <= value && value <= self.to
+    }
+
+    /// Number of elements, `None` when it doesn't fit in `T`.
+    #[must_use]
+    pub fn len(&self) -> Option<T> {
+        self.to.checked_sub(&self.from)?.checked_add(&T::one())
+    }
+
+    /// Splits into values `<= value` and values `> value`.
+    #[must_use]
+    pub fn split_at(&self, value: T) -> (Option<Self>, Option<Self>) {
+        let left = Interval::new(self.from, self.to.min(value));
+        let right = value
+            .checked_add(&T::one())
+            .and_then(|next| Interval::new(self.from.max(next), self.to));
+        (left, right)
+    }
+
+    #[must_use]
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        Interval::new(self.from.max(other.from), self.to.min(other.to))
+    }
+
+    /// Parts of `self` not covered by `other`, in increasing order.
+    #[must_use]
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        if self.intersect(other).is_none() {
+            return vec![*self];
+        }
+        let left = other
+            .from
+            .checked_sub(&T::one())
+            .and_then(|to| Interval::new(self.from, to));
+        let right = other
+            .to
+            .checked_add(&T::one())
+            .and_then(|from| Interval::new(from, self.to));
+        left.into_iter().chain(right).collect()
+    }
+
+    /// Sorted, disjoint and non-adjacent intervals covering the same values as `intervals`.
+    #[must_use]
+    pub fn union(intervals: &[Self]) -> Vec<Self> {
+        let mut sorted = intervals.to_vec();
+        sorted.sort_by_key(|interval| interval.from);
+        let mut result: Vec<Self> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match result.last_mut() {
+                Some(last) if last.to == T::max_value() || interval.from <= last.to + T::one() => {
+                    last.to = last.to.max(interval.to);
+                }
+                _ => result.push(interval),
+            }
+        }
+        result
+    }
+}
+
+/// Axis-aligned box, a product of one interval per axis.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct IntervalBox<T> {
+    pub axes: Vec<Interval<T>>,
+}
+
+impl<T: PrimInt> IntervalBox<T> {
+    #[must_use]
+    pub fn new(axes: Vec<Interval<T>>) -> Self {
+        IntervalBox { axes }
+    }
+
+    /// Box with the same interval on each of `dimensions` axes.
+    #[must_use]
+    pub fn cube(interval: Interval<T>, dimensions: usize) -> Self {
+        IntervalBox {
+            axes: vec![interval; dimensions],
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: &[T]) -> bool {
+        self.axes.len() == point.len()
+            && self
+                .axes
+                .iter()
+                .zip(point)
+                .all(|(interval, value)| interval.contains(*value))
+    }
+
+    /// Number of points inside, `None` when it doesn't fit in `T`.
+    #[must_use]
+    pub fn volume(&self) -> Option<T> {
+        self.axes
+            .iter()
+            .try_fold(T::one(), |acc, interval| acc.checked_mul(&interval.len()?))
+    }
+
+    /// Splits on `axis` into points with coordinate `<= value` and `> value`.
+    #[must_use]
+    pub fn split_at(&self, axis: usize, value: T) -> (Option<Self>, Option<Self>) {
+        let (left, right) = self.axes[axis].split_at(value);
+        (
+            left.map(|interval| self.with_axis(axis, interval)),
+            right.map(|interval| self.with_axis(axis, interval)),
+        )
+    }
+
+    #[must_use]
+    pub fn with_axis(&self, axis: usize, interval: Interval<T>) -> Self {
+        let mut result = self.clone();
+        result.axes[axis] = interval;
+        result
+    }
+
+    #[must_use]
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        self.axes
+            .iter()
+            .zip(&other.axes)
+            .map(|(a, b)| a.intersect(b))
+            .collect::<Option<Vec<Interval<T>>>>()
+            .map(IntervalBox::new)
+    }
+
+    /// Disjoint boxes covering the points of `self` which are not in `other`.
+    #[must_use]
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let Some(common) = self.intersect(other) else {
+            return vec![self.clone()];
+        };
+        // Peel off slabs outside of the common part one axis at a time
+        let mut result = Vec::new();
+        let mut rest = self.clone();
+        for (axis, common_interval) in common.axes.iter().enumerate() {
+            for interval in rest.axes[axis].subtract(common_interval) {
+                result.push(rest.with_axis(axis, interval));
+            }
+            rest = rest.with_axis(axis, *common_interval);
+        }
+        result
+    }
+
+    /// Disjoint boxes covering the same points as `boxes`.
+    #[must_use]
+    pub fn union(boxes: &[Self]) -> Vec<Self> {
+        let mut result: Vec<Self> = Vec::new();
+        for new_box in boxes {
+            let mut pieces = vec![new_box.clone()];
+            for existing in &result {
+                pieces = pieces
+                    .iter()
+                    .flat_map(|piece| piece.subtract(existing))
+                    .collect();
+            }
+            result.extend(pieces);
+        }
+        result
+    }
+
+    /// Number of points covered by the union of `boxes`, `None` on overflow.
+    #[must_use]
+    pub fn total_volume(boxes: &[Self]) -> Option<T> {
+        IntervalBox::union(boxes)
+            .iter()
+            .try_fold(T::zero(), |acc, b| acc.checked_add(&b.volume()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn interval(from: i32, to: i32) -> Interval<i32> {
+        Interval::new(from, to).unwrap()
+    }
+
+    #[test]
+    fn interval_basics() {
+        assert_eq!(None, Interval::new(3, 2));
+        assert_eq!(Some(1), interval(2, 2).len());
+        assert_eq!(None, Interval::new(i32::MIN, i32::MAX).unwrap().len());
+        assert!(interval(2, 5).contains(5));
+        assert!(!interval(2, 5).contains(6));
+    }
+
+    #[test]
+    fn interval_split_at() {
+        assert_eq!(
+            (Some(interval(1, 3)), Some(interval(4, 9))),
+            interval(1, 9).split_at(3)
+        );
+        assert_eq!((None, Some(interval(1, 9))), interval(1, 9).split_at(0));
+        assert_eq!((Some(interval(1, 9)), None), interval(1, 9).split_at(9));
+        assert_eq!(
+            (Some(interval(1, i32::MAX)), None),
+            interval(1, i32::MAX).split_at(i32::MAX)
+        );
+    }
+
+    #[test]
+    fn interval_intersect_and_subtract() {
+        assert_eq!(
+            Some(interval(3, 5)),
+            interval(1, 5).intersect(&interval(3, 9))
+        );
+        assert_eq!(None, interval(1, 2).intersect(&interval(3, 9)));
+        assert_eq!(
+            vec![interval(1, 2), interval(6, 9)],
+            interval(1, 9).subtract(&interval(3, 5))
+        );
+        assert_eq!(
+            vec![interval(1, 2)],
+            interval(1, 9).subtract(&interval(3, 12))
+        );
+        assert_eq!(
+            Vec::<Interval<i32>>::new(),
+            interval(4, 5).subtract(&interval(3, 12))
+        );
+        assert_eq!(
+            vec![interval(1, 2)],
+            interval(1, 2).subtract(&interval(3, 12))
+        );
+    }
+
+    #[test]
+    fn interval_union() {
+        assert_eq!(
+            vec![interval(-3, 6), interval(8, 8)],
+            Interval::union(&[
+                interval(5, 6),
+                interval(8, 8),
+                interval(-3, 0),
+                interval(1, 5)
+            ])
+        );
+        assert_eq!(
+            vec![interval(0, i32::MAX)],
+            Interval::union(&[interval(0, i32::MAX), interval(5, i32::MAX)])
+        );
+    }
+
+    #[test]
+    fn interval_operations_match_sets() {
+        for a_from in -3..3 {
+            for a_to in a_from..4 {
+                for b_from in -3..3 {
+                    for b_to in b_from..4 {
+                        let a = interval(a_from, a_to);
+                        let b = interval(b_from, b_to);
+                        for x in -5..6 {
+                            let in_a = a.contains(x);
+                            let in_b = b.contains(x);
+                            let in_difference = a.subtract(&b).iter().any(|i| i.contains(x));
+                            let in_intersection = a.intersect(&b).is_some_and(|i| i.contains(x));
+                            let in_union = Interval::union(&[a, b]).iter().any(|i| i.contains(x));
+                            assert_eq!(in_a && !in_b, in_difference);
+                            assert_eq!(in_a && in_b, in_intersection);
+                            assert_eq!(in_a || in_b, in_union);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn box_split_and_volume() {
+        let cube = IntervalBox::cube(Interval::new(1_u64, 4000).unwrap(), 4);
+        assert_eq!(Some(256_000_000_000_000), cube.volume());
+        let (left, right) = cube.split_at(1, 500);
+        assert_eq!(Interval::new(1, 500), left.map(|b| b.axes[1]));
+        assert_eq!(Interval::new(501, 4000), right.map(|b| b.axes[1]));
+        let huge = IntervalBox::cube(Interval::new(0_u64, u64::MAX - 1).unwrap(), 2);
+        assert_eq!(None, huge.volume());
+    }
+
+    #[test]
+    fn box_subtract_and_union() {
+        let a = IntervalBox::new(vec![interval(0, 9), interval(0, 9)]);
+        let b = IntervalBox::new(vec![interval(5, 14), interval(5, 14)]);
+        let difference = a.subtract(&b);
+        assert_eq!(
+            Some(75),
+            difference
+                .iter()
+                .map(|d| d.volume().unwrap())
+                .reduce(|x, y| x + y)
+        );
+        assert!(difference.iter().all(|d| d.intersect(&b).is_none()));
+        assert_eq!(
+            Some(175),
+            IntervalBox::total_volume(&[a.clone(), b.clone()])
+        );
+        assert_eq!(
+            Some(100),
+            IntervalBox::total_volume(&[a.clone(), a.clone()])
+        );
+        assert_eq!(Some(0), IntervalBox::<i32>::total_volume(&[]));
+
+        let pieces = IntervalBox::union(&[a.clone(), b.clone()]);
+        for x in -1..16 {
+            for y in -1..16 {
+                let expected = usize::from(a.contains(&[x, y]) || b.contains(&[x, y]));
+                let covering = pieces.iter().filter(|p| p.contains(&[x, y])).count();
+                assert_eq!(expected, covering);
+            }
+        }
+    }
+}
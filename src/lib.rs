@@ -1,6 +1,12 @@
 #![allow(dead_code, clippy::missing_panics_doc, clippy::implicit_hasher)]
 #![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::non_std_lazy_statics)]
 
+pub mod assignment;
+pub mod graph;
+pub mod interval;
+pub mod math;
+pub mod polygon;
 pub mod y2017;
 pub mod y2018;
 pub mod y2019;
@@ -0,0 +1,236 @@
+use num::{PrimInt, Unsigned};
+
+/// Greatest common divisor. `gcd(0, 0)` is 0.
+#[must_use]
+pub fn gcd<T: PrimInt + Unsigned>(mut a: T, mut b: T) -> T {
+    while !b.is_zero() {
+        let rest = a % b;
+        a = b;
+        b = rest;
+    }
+    a
+}
+
+/// Least common multiple, `None` when the result does not fit in `T`.
+#[must_use]
+pub fn checked_lcm<T: PrimInt + Unsigned>(a: T, b: T) -> Option<T> {
+    if a.is_zero() || b.is_zero() {
+        return Some(T::zero());
+    }
+    (a / gcd(a, b)).checked_mul(&b)
+}
+
+/// # Panics
+/// When the result does not fit in `T`.
+#[must_use]
+pub fn lcm<T: PrimInt + Unsigned>(a: T, b: T) -> T {
+    checked_lcm(a, b).expect("lcm overflow")
+}
+
+#[must_use]
+pub fn gcd_of<T: PrimInt + Unsigned>(values: &[T]) -> T {
+    values.iter().fold(T::zero(), |acc, &x| gcd(acc, x))
+}
+
+/// Least common multiple of all values (1 for an empty slice), `None` on overflow.
+/// Use `u128` values for cycles whose product may not fit in `u64`.
+#[must_use]
+pub fn checked_lcm_of<T: PrimInt + Unsigned>(values: &[T]) -> Option<T> {
+    values
+        .iter()
+        .try_fold(T::one(), |acc, &x| checked_lcm(acc, x))
+}
+
+/// # Panics
+/// When the result does not fit in `T`.
+#[must_use]
+pub fn lcm_of<T: PrimInt + Unsigned>(values: &[T]) -> T {
+    checked_lcm_of(values).expect("lcm overflow")
+}
+
+/// `base ^ exp mod modulus`, intermediate products are kept in `u128`.
+///
+/// # Panics
+/// When `modulus` is 0.
+#[must_use]
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    assert!(modulus != 0, "modulus must be positive");
+    let modulus = u128::from(modulus);
+    let mut base = u128::from(base) % modulus;
+    let mut result = 1 % modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    u64::try_from(result).unwrap()
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+#[must_use]
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_rem, mut rem) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while rem != 0 {
+        let quotient = old_rem / rem;
+        (old_rem, rem) = (rem, old_rem - quotient * rem);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_rem < 0 {
+        (-old_rem, -old_x, -old_y)
+    } else {
+        (old_rem, old_x, old_y)
+    }
+}
+
+/// Multiplicative inverse of `a` modulo `modulus`, `None` when they are not coprime.
+#[must_use]
+pub fn mod_inverse(a: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(i128::from(a), i128::from(modulus));
+    if g != 1 {
+        return None;
+    }
+    u64::try_from(x.rem_euclid(i128::from(modulus))).ok()
+}
+
+/// Largest `r` such that `r * r <= n`, computed without floating point.
+#[must_use]
+pub fn isqrt<T: PrimInt + Unsigned>(n: T) -> T {
+    let two = T::one() + T::one();
+    if n < two {
+        return n;
+    }
+    // Start above the root so that Newton's iteration decreases monotonically.
+    let bits = T::zero().count_zeros() - n.leading_zeros();
+    let mut x = T::one().unsigned_shl(bits.div_ceil(2));
+    loop {
+        let y = (x + n / x) / two;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(
+            2 * 3 * 7 * 5 * 5,
+            gcd(
+                2 * 5 * 7 * 5 * 3 * 11 * 17_u64,
+                2 * 3 * 7 * 5 * 5 * 8 * 7 * 3 * 5 * 7
+            )
+        );
+        assert_eq!(6, gcd(0_u64, 6));
+        assert_eq!(6, gcd(6_u64, 0));
+        assert_eq!(0, gcd(0_u64, 0));
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(2 * 3 * 7 * 5 * 5, lcm(2 * 3 * 5_u64, 7 * 5 * 5 * 3));
+        assert_eq!(0, lcm(0_u64, 7));
+    }
+
+    #[test]
+    fn gcd_lcm_properties() {
+        for a in 0..150_u64 {
+            for b in 0..150_u64 {
+                let g = gcd(a, b);
+                assert_eq!(g, gcd(b, a));
+                if g != 0 {
+                    assert_eq!(0, a % g);
+                    assert_eq!(0, b % g);
+                    assert_eq!(1, gcd(a / g, b / g));
+                }
+                assert_eq!(a * b, g * lcm(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn lcm_overflow_is_detected() {
+        assert_eq!(None, checked_lcm(u64::MAX, u64::MAX - 1));
+        assert_eq!(Some(u64::MAX), checked_lcm(u64::MAX, u64::MAX));
+        assert_eq!(None, checked_lcm_of(&[1u8 << 7, 3]));
+        let big = u128::from(u64::MAX);
+        assert_eq!(Some(big * (big - 1)), checked_lcm_of(&[big, big - 1]));
+    }
+
+    #[test]
+    fn slice_helpers() {
+        assert_eq!(1, lcm_of::<usize>(&[]));
+        assert_eq!(0, gcd_of::<usize>(&[]));
+        assert_eq!(2772, lcm_of(&[18_u64, 28, 44]));
+        assert_eq!(4, gcd_of(&[12_u32, 8, 20]));
+    }
+
+    #[test]
+    fn mod_pow_matches_naive() {
+        for modulus in 1..40_u64 {
+            for base in 0..40_u64 {
+                let mut expected = 1 % modulus;
+                for exp in 0..20_u64 {
+                    assert_eq!(expected, mod_pow(base, exp, modulus));
+                    expected = expected * base % modulus;
+                }
+            }
+        }
+        assert_eq!(1, mod_pow(u64::MAX - 1, u64::MAX - 1, u64::MAX));
+    }
+
+    #[test]
+    fn mod_inverse_properties() {
+        for modulus in 1..100_u64 {
+            for a in 0..modulus {
+                match mod_inverse(a, modulus) {
+                    Some(inverse) => {
+                        assert_eq!(1 % modulus, a * inverse % modulus);
+                        assert!(inverse < modulus);
+                    }
+                    None => assert_ne!(1, gcd(a, modulus)),
+                }
+            }
+        }
+        assert_eq!(None, mod_inverse(3, 0));
+    }
+
+    #[test]
+    fn extended_gcd_properties() {
+        for a in -60..60_i128 {
+            for b in -60..60_i128 {
+                let (g, x, y) = extended_gcd(a, b);
+                assert!(g >= 0);
+                assert_eq!(g, a * x + b * y);
+            }
+        }
+    }
+
+    #[test]
+    fn isqrt_properties() {
+        for n in 0..20_000_u64 {
+            let root = isqrt(n);
+            assert!(root * root <= n);
+            assert!((root + 1) * (root + 1) > n);
+        }
+        for root in [1_u64 << 20, 3_037_000_499, u64::from(u32::MAX)] {
+            assert_eq!(root, isqrt(root * root));
+            assert_eq!(root - 1, isqrt(root * root - 1));
+        }
+        assert_eq!(u64::from(u32::MAX), isqrt(u64::MAX));
+        assert_eq!(u128::from(u64::MAX), isqrt(u128::MAX));
+        assert_eq!(15, isqrt(u8::MAX));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::math;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    #[must_use]
+    pub fn new(x: i64, y: i64) -> Self {
+        Point { x, y }
+    }
+}
+
+/// Direction of travel along the vertices, with the y axis pointing up.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    /// Zero area, e.g. all vertices on one line.
+    Degenerate,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// Polygon with vertices on integer coordinates. The last vertex connects back to the first one.
+///
+/// Area and point counts use `i64`, so coordinates should stay well below `2^31`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// A last vertex equal to the first one, as produced by walking a closed path, is dropped.
+    #[must_use]
+    pub fn new(mut vertices: Vec<Point>) -> Self {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Polygon { vertices }
+    }
+
+    #[must_use]
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Pairs of consecutive vertices, including the closing edge.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    }
+
+    /// Twice the signed area by the shoelace formula, positive for counter-clockwise polygons.
+    /// Doubling keeps it an integer.
+    #[must_use]
+    pub fn doubled_signed_area(&self) -> i64 {
+        self.edges().map(|(a, b)| a.x * b.y - a.y * b.x).sum()
+    }
+
+    /// Area rounded down; lattice polygons may have half-integer areas.
+    #[must_use]
+    pub fn area(&self) -> u64 {
+        self.doubled_signed_area().unsigned_abs() / 2
+    }
+
+    #[must_use]
+    pub fn orientation(&self) -> Orientation {
+        match self.doubled_signed_area().signum() {
+            1 => Orientation::CounterClockwise,
+            -1 => Orientation::Clockwise,
+            _ => Orientation::Degenerate,
+        }
+    }
+
+    /// Number of lattice points on the edges. An edge holds `gcd(|dx|, |dy|)` of them when one of
+    /// its ends is left out.
+    #[must_use]
+    pub fn boundary_points(&self) -> u64 {
+        self.edges()
+            .map(|(a, b)| math::gcd((b.x - a.x).unsigned_abs(), (b.y - a.y).unsigned_abs()))
+            .sum()
+    }
+
+    /// Number of lattice points strictly inside by Pick's theorem, `A = I + B / 2 - 1`. Only
+    /// meaningful for simple polygons; `None` for degenerate ones enclosing no area and when the
+    /// formula comes out negative, which only happens for polygons crossing themselves.
+    #[must_use]
+    pub fn interior_points(&self) -> Option<u64> {
+        let doubled_area = self.doubled_signed_area().unsigned_abs();
+        if doubled_area == 0 {
+            return None;
+        }
+        (doubled_area + 2)
+            .checked_sub(self.boundary_points())
+            .map(|doubled| doubled / 2)
+    }
+
+    /// Lattice points inside or on the boundary, e.g. the cells of a dug out lagoon. `None` like
+    /// [`Polygon::interior_points`].
+    #[must_use]
+    pub fn covered_points(&self) -> Option<u64> {
+        Some(self.interior_points()? + self.boundary_points())
+    }
+
+    #[must_use]
+    pub fn locate(&self, point: Point) -> Location {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+            if cross == 0
+                && a.x.min(b.x) <= point.x
+                && point.x <= a.x.max(b.x)
+                && a.y.min(b.y) <= point.y
+                && point.y <= a.y.max(b.y)
+            {
+                return Location::Boundary;
+            }
+            // Ray to the right; half-open edges count vertices on the ray once
+            if (a.y > point.y) != (b.y > point.y) {
+                let crosses_right = if b.y > a.y { cross > 0 } else { cross < 0 };
+                if crosses_right {
+                    inside = !inside;
+                }
+            }
+        }
+        if inside {
+            Location::Inside
+        } else {
+            Location::Outside
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn polygon(points: &[(i64, i64)]) -> Polygon {
+        Polygon::new(points.iter().map(|(x, y)| Point::new(*x, *y)).collect())
+    }
+
+    #[test]
+    fn square() {
+        let square = polygon(&[(0, 0), (4, 0), (4, 4), (0, 4), (0, 0)]);
+        assert_eq!(4, square.vertices().len());
+        assert_eq!(32, square.doubled_signed_area());
+        assert_eq!(16, square.area());
+        assert_eq!(Orientation::CounterClockwise, square.orientation());
+        assert_eq!(16, square.boundary_points());
+        assert_eq!(Some(9), square.interior_points());
+        assert_eq!(Some(25), square.covered_points());
+
+        let reversed = polygon(&[(0, 0), (0, 4), (4, 4), (4, 0)]);
+        assert_eq!(-32, reversed.doubled_signed_area());
+        assert_eq!(Orientation::Clockwise, reversed.orientation());
+        assert_eq!(Some(9), reversed.interior_points());
+    }
+
+    #[test]
+    fn slanted_edges() {
+        let triangle = polygon(&[(0, 0), (4, 2), (0, 4)]);
+        assert_eq!(8, triangle.area());
+        assert_eq!(2 + 2 + 4, triangle.boundary_points());
+        assert_eq!(Some(5), triangle.interior_points());
+        let half = polygon(&[(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(1, half.doubled_signed_area());
+        assert_eq!(Some(0), half.interior_points());
+        let line = polygon(&[(0, 0), (3, 3)]);
+        assert_eq!(Orientation::Degenerate, line.orientation());
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        // Going there and back counts every edge point twice but encloses nothing
+        let line = polygon(&[(0, 0), (3, 3)]);
+        assert_eq!(6, line.boundary_points());
+        assert_eq!(None, line.interior_points());
+        assert_eq!(None, line.covered_points());
+        assert_eq!(None, polygon(&[(2, 2)]).interior_points());
+        assert_eq!(None, polygon(&[(0, 0), (1, 0), (4, 0)]).covered_points());
+    }
+
+    #[test]
+    fn locate_matches_pick() {
+        // Rectilinear polygon shaped like a U
+        let u = polygon(&[
+            (0, 0),
+            (6, 0),
+            (6, 5),
+            (4, 5),
+            (4, 2),
+            (2, 2),
+            (2, 5),
+            (0, 5),
+        ]);
+        let mut inside = 0;
+        let mut boundary = 0;
+        for x in -1..8 {
+            for y in -1..7 {
+                match u.locate(Point::new(x, y)) {
+                    Location::Inside => inside += 1,
+                    Location::Boundary => boundary += 1,
+                    Location::Outside => {}
+                }
+            }
+        }
+        assert_eq!(u.interior_points(), Some(inside));
+        assert_eq!(u.boundary_points(), boundary);
+        assert_eq!(Location::Outside, u.locate(Point::new(3, 4)));
+        assert_eq!(Location::Inside, u.locate(Point::new(1, 4)));
+        assert_eq!(Location::Boundary, u.locate(Point::new(3, 2)));
+        assert_eq!(Location::Outside, u.locate(Point::new(-1, 2)));
+    }
+}
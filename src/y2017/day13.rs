@@ -14,7 +14,7 @@ struct Layer {
 
 impl Layer {
     fn new(depth: usize, range: usize) -> Self {
-        let scanner_pos = if range == 0 { std::usize::MAX } else { 0 };
+        let scanner_pos = if range == 0 { usize::MAX } else { 0 };
         Layer {
             depth,
             range,
@@ -207,7 +207,7 @@ mod tests {
     fn move_scanner_empty_layer() {
         let mut layer = Layer::new(3, 0);
         layer.move_scanner();
-        assert_eq!(std::usize::MAX, layer.scanner_pos);
+        assert_eq!(usize::MAX, layer.scanner_pos);
     }
 
     #[test]
@@ -1,3 +1,4 @@
+use crate::math;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::ops;
@@ -15,6 +16,16 @@ impl ops::Add for Vector3 {
     }
 }
 
+impl ops::Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let Vector3(x1, y1, z1) = self;
+        let Vector3(x2, y2, z2) = rhs;
+        Vector3(x1 - x2, y1 - y2, z1 - z2)
+    }
+}
+
 impl ops::AddAssign for Vector3 {
     fn add_assign(&mut self, rhs: Self) {
         let Vector3(x, y, z) = rhs;
@@ -24,10 +35,10 @@ impl ops::AddAssign for Vector3 {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Direction {
-    GettingCloser,
-    FlyingAway,
+impl Vector3 {
+    fn components(self) -> [isize; 3] {
+        [self.0, self.1, self.2]
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -36,19 +47,15 @@ struct Particle {
     velocity: Vector3,
     acceleration: Vector3,
     id: usize,
-    moving_direction: Direction,
-    rate_of_position_change: isize,
 }
 
 impl Particle {
     fn new(id: usize, position: Vector3, velocity: Vector3, acceleration: Vector3) -> Self {
         Particle {
-            id,
             position,
             velocity,
             acceleration,
-            moving_direction: Direction::GettingCloser,
-            rate_of_position_change: 0,
+            id,
         }
     }
 
@@ -57,23 +64,38 @@ impl Particle {
     }
 
     fn move_particle(&mut self) {
-        let old_position = self.distance_from_origin();
         self.velocity += self.acceleration;
         self.position += self.velocity;
-        let new_position = self.distance_from_origin();
-
-        if new_position < old_position {
-            self.moving_direction = Direction::GettingCloser;
-            self.rate_of_position_change = old_position - new_position;
-        } else {
-            self.moving_direction = Direction::FlyingAway;
-            self.rate_of_position_change = new_position - old_position;
-        }
     }
 
     fn acceleration_rate(&self) -> isize {
         self.acceleration.0.abs() + self.acceleration.1.abs() + self.acceleration.2.abs()
     }
+
+    /// Doubled distance after `t` ticks is `sum(|a * t^2 + (2v + a) * t + 2p|)` over the axes.
+    /// Eventually every axis keeps the sign of its first non-zero value out of `(a, v, p)`, so from
+    /// then on the distance is a single quadratic `|a| * t^2 + (2 * sv + |a|) * t + 2 * sp`, where
+    /// `sv` and `sp` are velocity and position projected on those signs. Comparing
+    /// `(|a|, sv, sp)` lexicographically orders particles by how close they stay in the long run.
+    fn long_term_distance_key(&self) -> (isize, isize, isize) {
+        let mut velocity_along = 0;
+        let mut position_along = 0;
+        for ((a, v), p) in self
+            .acceleration
+            .components()
+            .into_iter()
+            .zip(self.velocity.components())
+            .zip(self.position.components())
+        {
+            let sign = [a, v, p]
+                .into_iter()
+                .find(|x| *x != 0)
+                .map_or(0, isize::signum);
+            velocity_along += sign * v;
+            position_along += sign * p;
+        }
+        (self.acceleration_rate(), velocity_along, position_along)
+    }
 }
 
 lazy_static! {
@@ -107,81 +129,123 @@ fn parse_data(input: &str) -> Vec<Particle> {
         .collect()
 }
 
-fn find_particles_with_minimal_acc(particles: Vec<Particle>) -> Vec<Particle> {
-    let minimal_acceleration_rate = particles
+/// Particles that stay equally close forever are resolved by the lower id.
+///
+/// # Panics
+/// When there are no particles in the input.
+#[must_use]
+pub fn part1(input: &str) -> usize {
+    parse_data(input)
         .iter()
-        .min_by(|p1, p2| p1.acceleration_rate().cmp(&p2.acceleration_rate()))
-        .map(Particle::acceleration_rate)
-        .unwrap();
-    particles
-        .into_iter()
-        .filter(|p| p.acceleration_rate() == minimal_acceleration_rate)
-        .collect()
-}
-
-fn find_particle_with_minimal_rate(particles: &[Particle]) -> usize {
-    particles
-        .iter()
-        .min_by(|p1, p2| p1.rate_of_position_change.cmp(&p2.rate_of_position_change))
+        .min_by_key(|p| (p.long_term_distance_key(), p.id))
         .map(|p| p.id)
         .unwrap()
 }
 
-#[must_use] pub fn part1(input: &str) -> usize {
-    let particles: Vec<Particle> = parse_data(input);
-    let mut minimal_acceleration_particles = find_particles_with_minimal_acc(particles);
+/// Ticks (counted from 1) at which a single axis of two particles coincides.
+#[derive(Debug, PartialEq)]
+enum AxisCollision {
+    Always,
+    At(Vec<isize>),
+}
 
-    let mut are_getting_closer = true;
+/// After `t` ticks the position is `p + v * t + a * t * (t + 1) / 2`, so the doubled difference of
+/// two particles on one axis is `a * t^2 + b * t + c` with `a = da`, `b = 2 * dv + da` and
+/// `c = 2 * dp`. Only positive integer roots are collisions.
+fn axis_collision_ticks(a: isize, b: isize, c: isize) -> AxisCollision {
+    let mut ticks: Vec<isize> = if a == 0 {
+        if b == 0 {
+            return if c == 0 {
+                AxisCollision::Always
+            } else {
+                AxisCollision::At(Vec::new())
+            };
+        }
+        if c % b == 0 {
+            vec![-c / b]
+        } else {
+            Vec::new()
+        }
+    } else {
+        let discriminant = b * b - 4 * a * c;
+        if discriminant < 0 {
+            return AxisCollision::At(Vec::new());
+        }
+        let root = isize::try_from(math::isqrt(discriminant.unsigned_abs())).unwrap();
+        if root * root != discriminant {
+            return AxisCollision::At(Vec::new());
+        }
+        [-b - root, -b + root]
+            .into_iter()
+            .filter(|numerator| numerator % (2 * a) == 0)
+            .map(|numerator| numerator / (2 * a))
+            .collect()
+    };
+    ticks.retain(|t| *t >= 1);
+    ticks.sort_unstable();
+    ticks.dedup();
+    AxisCollision::At(ticks)
+}
 
-    while are_getting_closer {
-        let mut directions: Vec<Direction> =
-            Vec::with_capacity(minimal_acceleration_particles.len());
-        for particle in &mut minimal_acceleration_particles {
-            particle.move_particle();
-            directions.push(particle.moving_direction);
+fn collision_tick(p1: &Particle, p2: &Particle) -> Option<isize> {
+    let dp = (p1.position - p2.position).components();
+    let dv = (p1.velocity - p2.velocity).components();
+    let da = (p1.acceleration - p2.acceleration).components();
+
+    // `None` means that every tick is still a candidate
+    let mut candidates: Option<Vec<isize>> = None;
+    for axis in 0..3 {
+        if let AxisCollision::At(ticks) =
+            axis_collision_ticks(da[axis], 2 * dv[axis] + da[axis], 2 * dp[axis])
+        {
+            candidates = Some(match candidates {
+                None => ticks,
+                Some(current) => current.into_iter().filter(|t| ticks.contains(t)).collect(),
+            });
         }
-        are_getting_closer = directions.iter().any(|d| *d == Direction::GettingCloser);
     }
 
-    find_particle_with_minimal_rate(&minimal_acceleration_particles)
+    match candidates {
+        None => Some(1),
+        Some(ticks) => ticks.into_iter().min(),
+    }
 }
 
-#[must_use] pub fn part2(input: &str) -> usize {
-    let mut particles: Vec<Particle> = parse_data(input);
-
-    loop {
-        let mut directions: Vec<Direction> = Vec::with_capacity(particles.len());
-        for particle in &mut particles {
-            particle.move_particle();
-            directions.push(particle.moving_direction);
+/// Every pair that meets at some tick, ordered by the tick of their first meeting.
+fn find_collisions(particles: &[Particle]) -> Vec<(isize, usize, usize)> {
+    let mut collisions = Vec::new();
+    for (i, p1) in particles.iter().enumerate() {
+        for (j, p2) in particles.iter().enumerate().skip(i + 1) {
+            if let Some(tick) = collision_tick(p1, p2) {
+                collisions.push((tick, i, j));
+            }
         }
+    }
+    collisions.sort_unstable();
+    collisions
+}
 
-        let to_remove: Vec<Vector3> = particles
+fn count_survivors(particles: &[Particle]) -> usize {
+    let mut destroyed = vec![false; particles.len()];
+    for same_tick in find_collisions(particles).chunk_by(|c1, c2| c1.0 == c2.0) {
+        // Particles destroyed in earlier ticks can't collide anymore, but all collisions from the
+        // same tick happen at once.
+        let colliding: Vec<usize> = same_tick
             .iter()
-            .filter(|p| {
-                let dups: Vec<Particle> = particles
-                    .iter()
-                    .filter(|p2| p2 != p && p2.position == p.position)
-                    .map(std::clone::Clone::clone)
-                    .collect();
-                !dups.is_empty()
-            })
-            .map(|p| p.position)
+            .filter(|(_, i, j)| !destroyed[*i] && !destroyed[*j])
+            .flat_map(|(_, i, j)| [*i, *j])
             .collect();
-
-        particles.retain(|p| !to_remove.contains(&p.position));
-
-        // TODO: this condition is not optimal and could be wrong but I have no idea how to easily check whether all
-        // particles are getting away from each other and this version yields the correct answer for a
-        // problem with my data set. velocity and acceleration vectors should point
-        // outward from each other and then particles could not collide. It can probably be expressed
-        // somehow using dot product.
-        if directions.iter().all(|d| *d == Direction::FlyingAway) {
-            break;
+        for i in colliding {
+            destroyed[i] = true;
         }
     }
+    destroyed.iter().filter(|d| !**d).count()
+}
 
-    particles.len()
+#[must_use]
+pub fn part2(input: &str) -> usize {
+    let particles: Vec<Particle> = parse_data(input);
+    count_survivors(&particles)
 }
 
 #[cfg(test)]
@@ -220,21 +284,16 @@ mod tests {
     fn move_particle_test() {
         let mut input = parse_row(0, "p=<113,-112,111>, v=<-13,12,11>, a=<3,2,-1>");
         input.move_particle();
-        let mut expected = parse_row(0, "p=<103,-98,121>, v=<-10,14,10>, a=<3,2,-1>");
-        expected.rate_of_position_change = 14;
+        let expected = parse_row(0, "p=<103,-98,121>, v=<-10,14,10>, a=<3,2,-1>");
         assert_eq!(expected, input);
-        assert_eq!(Direction::GettingCloser, input.moving_direction);
     }
 
     #[test]
     fn move_particle_test2() {
         let mut input = parse_row(0, "p=<1,1,1>, v=<1,1,1>, a=<1,1,1>");
         input.move_particle();
-        let mut expected = parse_row(0, "p=<3,3,3>, v=<2,2,2>, a=<1,1,1>");
-        expected.rate_of_position_change = 6;
-        expected.moving_direction = Direction::FlyingAway;
+        let expected = parse_row(0, "p=<3,3,3>, v=<2,2,2>, a=<1,1,1>");
         assert_eq!(expected, input);
-        assert_eq!(Direction::FlyingAway, input.moving_direction);
     }
 
     #[test]
@@ -243,6 +302,104 @@ mod tests {
         assert_eq!(6, parse_row(0, input).acceleration_rate());
     }
 
+    #[test]
+    fn long_term_distance_key_test() {
+        let particle = parse_row(0, "p=<-478,1930,2092>, v=<20,-35,-114>, a=<0,-4,2>");
+        // x follows velocity, y and z follow acceleration
+        assert_eq!(
+            (6, 20 + 35 - 114, -478 - 1930 + 2092),
+            particle.long_term_distance_key()
+        );
+        let resting = parse_row(0, "p=<0,-3,0>, v=<0,0,0>, a=<0,0,0>");
+        assert_eq!((0, 0, 3), resting.long_term_distance_key());
+    }
+
+    #[test]
+    fn test_case_part1() {
+        let input = "p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>
+p=<4,0,0>, v=<0,0,0>, a=<-2,0,0>";
+        assert_eq!(0, part1(input));
+    }
+
+    #[test]
+    fn part1_velocity_sign_change() {
+        // Both decelerate the same way, but the first one starts flying towards the origin and
+        // passes it, ending up further away after the turn.
+        let input = "p=<10,0,0>, v=<-5,0,0>, a=<-1,0,0>
+p=<-10,0,0>, v=<4,0,0>, a=<-1,0,0>";
+        assert_eq!(1, part1(input));
+    }
+
+    #[test]
+    fn part1_ties_resolved_by_position_then_id() {
+        let input = "p=<5,0,0>, v=<1,0,0>, a=<0,0,0>
+p=<0,3,0>, v=<0,1,0>, a=<0,0,0>
+p=<0,0,-3>, v=<0,0,-1>, a=<0,0,0>";
+        assert_eq!(1, part1(input));
+    }
+
+    #[test]
+    fn axis_collision_ticks_test() {
+        assert_eq!(AxisCollision::Always, axis_collision_ticks(0, 0, 0));
+        assert_eq!(AxisCollision::At(vec![]), axis_collision_ticks(0, 0, 4));
+        assert_eq!(AxisCollision::At(vec![2]), axis_collision_ticks(0, 2, -4));
+        assert_eq!(AxisCollision::At(vec![]), axis_collision_ticks(0, 3, -4));
+        assert_eq!(AxisCollision::At(vec![]), axis_collision_ticks(0, 2, 4));
+        // (t - 2)(t - 5)
+        assert_eq!(
+            AxisCollision::At(vec![2, 5]),
+            axis_collision_ticks(1, -7, 10)
+        );
+        // (t - 3)^2
+        assert_eq!(AxisCollision::At(vec![3]), axis_collision_ticks(1, -6, 9));
+        // (2t - 1)(t - 4)
+        assert_eq!(AxisCollision::At(vec![4]), axis_collision_ticks(2, -9, 4));
+        assert_eq!(AxisCollision::At(vec![]), axis_collision_ticks(1, 0, 2));
+    }
+
+    #[test]
+    fn collision_tick_test() {
+        let p1 = parse_row(0, "p=<-6,0,0>, v=<3,0,0>, a=<0,0,0>");
+        let p2 = parse_row(1, "p=<-4,0,0>, v=<2,0,0>, a=<0,0,0>");
+        let p3 = parse_row(2, "p=<3,0,0>, v=<-1,0,0>, a=<0,0,0>");
+        assert_eq!(Some(2), collision_tick(&p1, &p2));
+        assert_eq!(None, collision_tick(&p1, &p3));
+        assert_eq!(Some(1), collision_tick(&p1, &p1));
+    }
+
+    #[test]
+    fn collision_tick_with_acceleration() {
+        let p1 = parse_row(0, "p=<0,0,0>, v=<0,0,0>, a=<2,2,-2>");
+        let p2 = parse_row(1, "p=<6,6,-6>, v=<1,1,-1>, a=<1,1,-1>");
+        let mut s1 = p1.clone();
+        let mut s2 = p2.clone();
+        let mut tick = 0;
+        while s1.position != s2.position && tick < 100 {
+            s1.move_particle();
+            s2.move_particle();
+            tick += 1;
+        }
+        assert_eq!(Some(tick), collision_tick(&p1, &p2));
+    }
+
+    #[test]
+    fn test_case_part2() {
+        let input = "p=<-6,0,0>, v=<3,0,0>, a=<0,0,0>
+p=<-4,0,0>, v=<2,0,0>, a=<0,0,0>
+p=<-2,0,0>, v=<1,0,0>, a=<0,0,0>
+p=<3,0,0>, v=<-1,0,0>, a=<0,0,0>";
+        assert_eq!(1, part2(input));
+    }
+
+    #[test]
+    fn particles_destroyed_earlier_do_not_collide() {
+        // 0 and 1 meet at tick 1, so 2 has nothing left to hit at tick 2.
+        let input = "p=<0,0,0>, v=<1,0,0>, a=<0,0,0>
+p=<2,0,0>, v=<-1,0,0>, a=<0,0,0>
+p=<4,0,0>, v=<-1,0,0>, a=<0,0,0>";
+        assert_eq!(1, part2(input));
+    }
+
     #[test]
     fn vector_addition() {
         let v1 = Vector3(1, 4, 13);
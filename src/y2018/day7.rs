@@ -1,6 +1,9 @@
+use crate::graph::Graph;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::fmt::Write;
 
 type Vertex = char;
 
@@ -10,13 +13,6 @@ struct Edge {
     to: Vertex,
 }
 
-fn flip_edge(e: &Edge) -> Edge {
-    Edge {
-        from: e.to,
-        to: e.from,
-    }
-}
-
 lazy_static! {
     static ref RE: Regex =
         Regex::new(r"Step (?<from>.) must be finished before step (?<to>.) can begin.").unwrap();
@@ -30,138 +26,141 @@ fn parse_row(row: &str) -> Edge {
     }
 }
 
-fn find_next(edges: &Vec<Edge>) -> Vec<Vertex> {
-    let mut froms: HashSet<Vertex> = HashSet::new();
-    let mut tos: HashSet<Vertex> = HashSet::new();
-    for Edge { from, to } in edges {
-        froms.insert(*from);
-        tos.insert(*to);
-    }
-    froms.retain(|x| !tos.contains(x));
-    let mut possible_next_tasks = froms.into_iter().collect::<Vec<Vertex>>();
-    possible_next_tasks.sort_unstable();
-    possible_next_tasks
+fn build_graph(edges: &[Edge]) -> Graph<Vertex> {
+    Graph::from_edges(edges.iter().map(|edge| (edge.from, edge.to)))
 }
 
+/// # Panics
+#[must_use]
 pub fn part1(input: &str) -> String {
-    let mut edges: Vec<Edge> = input.lines().map(parse_row).collect();
-    let mut result = String::new();
-    let mut rest: Vec<Edge>;
-    let mut tail_handled = false;
-    while !edges.is_empty() {
-        let next = find_next(&edges)[0];
-        result.push(next);
-        (edges, rest) = edges.into_iter().partition(|x| x.from != next);
-
-        // this case handles the rest of the vertices when last requirement is met
-        if edges.is_empty() && !rest.is_empty() && !tail_handled {
-            edges = rest.iter().map(flip_edge).collect();
-            tail_handled = true;
-        }
-    }
-    result
+    let edges: Vec<Edge> = input.lines().map(parse_row).collect();
+    let graph = build_graph(&edges);
+    graph
+        .topological_sort_by_key(|step| *step)
+        .unwrap()
+        .into_iter()
+        .map(|id| *graph.label(id))
+        .collect()
 }
 
-fn time_for_task(v: Vertex, time_constant: u8) -> u8 {
-    (v as u8) + time_constant - 64
+fn time_for_task(v: Vertex, time_constant: u64) -> u64 {
+    u64::from(v) + time_constant - 64
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct Worker {
-    current_task: char,
-    time_to_finish: u8,
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Assignment {
+    step: Vertex,
+    worker: usize,
+    start: u64,
+    end: u64,
 }
 
-impl Worker {
-    fn finished(&self) -> bool {
-        self.time_to_finish == 0
-    }
+#[derive(Debug, PartialEq)]
+struct Schedule {
+    workers: usize,
+    completion_time: u64,
+    /// Ordered by end time, then by step.
+    assignments: Vec<Assignment>,
 }
 
-fn tasks_performed_by_other(workers: &[Worker], index: usize) -> HashSet<char> {
-    workers
+/// `(end, step, worker, start, vertex id)` of a running step.
+type Event = (u64, Vertex, usize, u64, usize);
+
+/// Runs the steps on `workers` workers. Whenever workers are idle, the alphabetically first
+/// available steps go to the lowest numbered of them. Returns `None` when some steps can never
+/// start, which happens with cyclic requirements or without workers.
+fn schedule(
+    graph: &Graph<Vertex>,
+    workers: usize,
+    duration: impl Fn(Vertex) -> u64,
+) -> Option<Schedule> {
+    let mut blocked_by = graph.in_degrees();
+    let mut available: BTreeSet<(Vertex, usize)> = blocked_by
         .iter()
         .enumerate()
-        .filter(|(i, _)| *i != index)
-        .map(|(_, worker)| worker.current_task)
-        .collect()
-}
+        .filter(|(_, count)| **count == 0)
+        .map(|(id, _)| (*graph.label(id), id))
+        .collect();
+    let mut idle: BTreeSet<usize> = (0..workers).collect();
+    let mut running: BinaryHeap<Reverse<Event>> = BinaryHeap::new();
+    let mut assignments = Vec::with_capacity(graph.len());
+    let mut time = 0;
 
-fn process_next_second(
-    edges: &mut Vec<Edge>,
-    workers: &mut Vec<Worker>,
-    time_constant: u8,
-) -> Vec<Edge> {
-    let mut rest: Vec<Edge> = Vec::new();
-    for i in 0..workers.len() {
-        if workers[i].finished() {
-            let other_workers_tasks = tasks_performed_by_other(workers, i);
-            let filtered_edges: Vec<Edge> = edges
-                .iter()
-                .filter(|x| x.from != workers[i].current_task)
-                .cloned()
-                .collect();
-            let potential_rest: Vec<Edge> = edges
-                .iter()
-                .filter(|x| x.from == workers[i].current_task)
-                .cloned()
-                .collect();
-            if !potential_rest.is_empty() {
-                rest = potential_rest;
-            };
-            if let Some(next_task) = find_next(&filtered_edges)
-                .iter()
-                .find(|&&x| !other_workers_tasks.contains(&x))
-            {
-                workers[i].current_task = *next_task;
-                workers[i].time_to_finish = time_for_task(*next_task, time_constant);
-            }
-            *edges = filtered_edges;
+    loop {
+        while let (Some(&worker), Some(&(step, id))) = (idle.first(), available.first()) {
+            idle.remove(&worker);
+            available.remove(&(step, id));
+            running.push(Reverse((time + duration(step), step, worker, time, id)));
         }
-        workers[i].time_to_finish = if workers[i].time_to_finish > 0 {
-            workers[i].time_to_finish - 1
-        } else {
-            workers[i].current_task = '.';
-            0
+        let Some(&Reverse((end, ..))) = running.peek() else {
+            break;
         };
+        time = end;
+        while running.peek().is_some_and(|Reverse(event)| event.0 == end) {
+            let Reverse((_, step, worker, start, id)) = running.pop().unwrap();
+            idle.insert(worker);
+            assignments.push(Assignment {
+                step,
+                worker,
+                start,
+                end,
+            });
+            for next in graph.successors(id) {
+                blocked_by[*next] -= 1;
+                if blocked_by[*next] == 0 {
+                    available.insert((*graph.label(*next), *next));
+                }
+            }
+        }
     }
-    rest
-}
 
-fn organize_work(mut edges: Vec<Edge>, time_constant: u8, number_of_workers: usize) -> u64 {
-    let mut time = 0;
-    let mut tail_handled = false;
-    let mut workers: Vec<Worker> = vec![
-        Worker {
-            current_task: '.',
-            time_to_finish: 0
-        };
-        number_of_workers
-    ];
-
-    let mut rest: Vec<Edge>;
-    while !edges.is_empty() {
-        rest = process_next_second(&mut edges, &mut workers, time_constant);
-        time += 1;
+    (assignments.len() == graph.len()).then_some(Schedule {
+        workers,
+        completion_time: time,
+        assignments,
+    })
+}
 
-        if edges.is_empty() && !rest.is_empty() && !tail_handled {
-            edges = rest.iter().map(flip_edge).collect();
-            tail_handled = true;
+impl Schedule {
+    /// Per-second table of what each worker does and which steps are done, laid out like the one
+    /// in the puzzle description.
+    fn timeline(&self) -> String {
+        let mut header = String::from("Second   ");
+        for worker in 1..=self.workers {
+            let _ = write!(header, "Worker {worker}   ");
         }
+        header.push_str("Done");
+
+        let mut lines = vec![header];
+        for second in 0..=self.completion_time {
+            let mut line = format!("{second:>4}     ");
+            for worker in 0..self.workers {
+                let step = self
+                    .assignments
+                    .iter()
+                    .find(|a| a.worker == worker && a.start <= second && second < a.end)
+                    .map_or('.', |a| a.step);
+                let _ = write!(line, "   {step}       ");
+            }
+            line.extend(
+                self.assignments
+                    .iter()
+                    .take_while(|a| a.end <= second)
+                    .map(|a| a.step),
+            );
+            lines.push(line.trim_end().to_owned());
+        }
+        lines.join("\n")
     }
-
-    if tail_handled {
-        time -= 2;
-    } else {
-        time -= 1;
-    }
-
-    time
 }
 
+/// # Panics
+#[must_use]
 pub fn part2(input: &str) -> u64 {
     let edges: Vec<Edge> = input.lines().map(parse_row).collect();
-    organize_work(edges, 60, 5)
+    schedule(&build_graph(&edges), 5, |step| time_for_task(step, 60))
+        .unwrap()
+        .completion_time
 }
 
 #[cfg(test)]
@@ -184,35 +183,16 @@ mod tests {
     }
 
     #[test]
-    fn find_next_test1() {
-        assert_eq!(
-            'A',
-            find_next(&vec![
-                Edge { from: 'A', to: 'B' },
-                Edge { from: 'A', to: 'C' }
-            ])[0]
-        );
-    }
-
-    #[test]
-    fn find_next_test2() {
-        assert_eq!(
-            'C',
-            find_next(&vec![
-                Edge { from: 'C', to: 'A' },
-                Edge { from: 'C', to: 'F' },
-                Edge { from: 'A', to: 'B' },
-                Edge { from: 'A', to: 'D' },
-                Edge { from: 'B', to: 'E' },
-                Edge { from: 'D', to: 'E' },
-                Edge { from: 'F', to: 'E' },
-            ])[0]
-        );
+    fn test_case_test() {
+        assert_eq!("CABDFE", part1(TEST_CASE_INPUT));
     }
 
     #[test]
-    fn test_case_test() {
-        assert_eq!("CABDFE", part1(TEST_CASE_INPUT));
+    fn steps_finishing_in_parallel() {
+        // Both branches end at their own last step, which the edge list alone hides
+        let input = "Step A must be finished before step B can begin.
+            Step C must be finished before step D can begin.";
+        assert_eq!("ABCD", part1(input));
     }
 
     #[test]
@@ -226,161 +206,56 @@ mod tests {
     }
 
     #[test]
-    fn flip_edge_test() {
-        assert_eq!(
-            Edge { from: 'x', to: 'D' },
-            flip_edge(&Edge { from: 'D', to: 'x' })
-        );
-    }
-
-    #[test]
-    fn tasks_performed_by_other_test() {
-        let workers = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'B',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'C',
-                time_to_finish: 10,
-            },
-        ];
-        let mut result = HashSet::new();
-        result.insert('A');
-        result.insert('C');
-        assert_eq!(result, tasks_performed_by_other(&workers, 1))
-    }
-
-    #[test]
-    fn process_next_second_time_flows() {
-        let mut edges = vec![Edge { from: 'A', to: 'B' }];
-        let mut workers = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'B',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'C',
-                time_to_finish: 10,
-            },
-        ];
-        let result = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: 'B',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: 'C',
-                time_to_finish: 9,
-            },
-        ];
-        let _ = process_next_second(&mut edges, &mut workers, 0);
-        assert_eq!(result, workers);
-    }
-
-    #[test]
-    fn process_next_second_selecting_new_task() {
-        let mut edges = vec![Edge { from: 'A', to: 'B' }];
-        let mut workers = vec![
-            Worker {
-                current_task: 'Z',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'W',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: '.',
-                time_to_finish: 0,
-            },
-        ];
-        let result = vec![
-            Worker {
-                current_task: 'Z',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: 'W',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: 'A',
-                time_to_finish: 5,
-            },
-        ];
-        let _ = process_next_second(&mut edges, &mut workers, 5);
-        assert_eq!(result, workers);
-    }
-
-    #[test]
-    fn process_next_second_cant_select_new_task() {
-        let mut edges = vec![Edge { from: 'A', to: 'B' }];
-        let mut workers = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: 'W',
-                time_to_finish: 10,
-            },
-            Worker {
-                current_task: '.',
-                time_to_finish: 0,
-            },
-        ];
-        let result = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: 'W',
-                time_to_finish: 9,
-            },
-            Worker {
-                current_task: '.',
-                time_to_finish: 0,
-            },
-        ];
-        let _ = process_next_second(&mut edges, &mut workers, 5);
-        assert_eq!(result, workers);
+    fn test_case2() {
+        let edges: Vec<Edge> = TEST_CASE_INPUT.lines().map(parse_row).collect();
+        let graph = build_graph(&edges);
+        let schedule = schedule(&graph, 2, |step| time_for_task(step, 0)).unwrap();
+        assert_eq!(15, schedule.completion_time);
+        let order: String = schedule.assignments.iter().map(|a| a.step).collect();
+        assert_eq!("CABFDE", order);
     }
 
     #[test]
-    fn process_next_second_result_test() {
-        let mut edges = vec![Edge { from: 'A', to: 'B' }];
-        let mut workers = vec![
-            Worker {
-                current_task: 'A',
-                time_to_finish: 0,
-            },
-            Worker {
-                current_task: '.',
-                time_to_finish: 0,
-            },
-        ];
-        let expected_result = edges.clone();
-        let restult = process_next_second(&mut edges, &mut workers, 5);
-        assert_eq!(expected_result, restult);
+    fn timeline_test() {
+        let edges: Vec<Edge> = TEST_CASE_INPUT.lines().map(parse_row).collect();
+        let graph = build_graph(&edges);
+        let schedule = schedule(&graph, 2, |step| time_for_task(step, 0)).unwrap();
+        let expected = "Second   Worker 1   Worker 2   Done
+   0        C          .
+   1        C          .
+   2        C          .
+   3        A          F       C
+   4        B          F       CA
+   5        B          F       CA
+   6        D          F       CAB
+   7        D          F       CAB
+   8        D          F       CAB
+   9        D          .       CABF
+  10        E          .       CABFD
+  11        E          .       CABFD
+  12        E          .       CABFD
+  13        E          .       CABFD
+  14        E          .       CABFD
+  15        .          .       CABFDE";
+        assert_eq!(expected, schedule.timeline());
     }
 
     #[test]
-    fn test_case2() {
-        let edges: Vec<Edge> = TEST_CASE_INPUT.lines().map(|x| parse_row(x)).collect();
-        assert_eq!(15, organize_work(edges, 0, 2));
+    fn schedule_with_other_workers_and_durations() {
+        let edges: Vec<Edge> = TEST_CASE_INPUT.lines().map(parse_row).collect();
+        let graph = build_graph(&edges);
+        // One worker does everything in the order of part 1
+        let schedule1 = schedule(&graph, 1, |step| time_for_task(step, 60)).unwrap();
+        let order: String = schedule1.assignments.iter().map(|a| a.step).collect();
+        assert_eq!(part1(TEST_CASE_INPUT), order);
+        assert_eq!(60 * 6 + 21, schedule1.completion_time);
+
+        // Enough workers turn the completion time into the longest path C, A, D, E
+        let schedule2 = schedule(&graph, 5, |_| 10).unwrap();
+        assert_eq!(40, schedule2.completion_time);
+
+        assert_eq!(None, schedule(&graph, 0, |_| 1));
+        let cycle = vec![Edge { from: 'A', to: 'B' }, Edge { from: 'B', to: 'A' }];
+        assert_eq!(None, schedule(&build_graph(&cycle), 2, |_| 1));
     }
 }
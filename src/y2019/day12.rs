@@ -1,3 +1,4 @@
+use crate::math;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -80,7 +81,7 @@ fn calculate_gravity_for_moon(moons: &[Moon], i: usize) -> Moon {
     }
 }
 
-fn calculate_gravity(moons: &Vec<Moon>) -> Vec<Moon> {
+fn calculate_gravity(moons: &[Moon]) -> Vec<Moon> {
     let mut result = Vec::new();
     for i in 0..moons.len() {
         let moon = calculate_gravity_for_moon(moons, i);
@@ -102,84 +103,93 @@ fn energy_after_n_steps(moons: Vec<Moon>, n: usize) -> u64 {
     })
 }
 
+#[must_use]
 pub fn part1(input: &str) -> u64 {
     let moons: Vec<Moon> = input.lines().map(parse_row).collect();
     energy_after_n_steps(moons, 1000)
 }
 
-fn lcm(x: u64, y: u64) -> u64 {
-    x * y / gcd(x, y)
+/// Positions and velocities of all moons along one axis. Axes don't influence each other, so
+/// each of them can be simulated on its own.
+#[derive(Debug, PartialEq, Clone)]
+struct AxisState {
+    positions: Vec<i64>,
+    velocities: Vec<i64>,
 }
 
-fn gcd(mut x: u64, mut y: u64) -> u64 {
-    let mut bigger = x;
-    let mut smaller = y;
-    if smaller > bigger {
-        std::mem::swap(&mut x, &mut y);
+impl AxisState {
+    fn step(&mut self) {
+        for (i, velocity) in self.velocities.iter_mut().enumerate() {
+            let position = self.positions[i];
+            *velocity += self
+                .positions
+                .iter()
+                .map(|other| (other - position).signum())
+                .sum::<i64>();
+        }
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += velocity;
+        }
     }
 
-    loop {
-        let res = bigger % smaller;
-        if res == 0 {
-            return smaller;
+    /// Number of steps until the state first repeats. A step can be undone, so the first
+    /// repeated state is always the initial one.
+    fn period(&self) -> u64 {
+        let mut state = self.clone();
+        let mut steps = 0;
+        loop {
+            state.step();
+            steps += 1;
+            if state == *self {
+                return steps;
+            }
         }
-
-        bigger = smaller;
-        smaller = res;
     }
 }
 
-fn find_cycle(moons: &[Moon]) -> u64 {
-    let mut x_cycle = 0;
-    let mut y_cycle = 0;
-    let mut z_cycle = 0;
-
-    let xs: Vec<i64> = moons.iter().map(|x| x.position.0).collect();
-    let ys: Vec<i64> = moons.iter().map(|x| x.position.1).collect();
-    let zs: Vec<i64> = moons.iter().map(|x| x.position.2).collect();
-
-    let mut counter = 0;
-
-    let mut current_moons: Vec<Moon> = moons.to_vec();
-    while x_cycle * y_cycle * z_cycle == 0 {
-        counter += 1;
-        current_moons = calculate_gravity(&current_moons);
-        for moon in &mut current_moons {
-            moon.move_one_timestep();
-        }
-        if x_cycle == 0
-            && current_moons
-                .iter()
-                .map(|x| x.position.0)
-                .collect::<Vec<i64>>()
-                == xs
-        {
-            x_cycle = counter + 1;
-        }
+fn axis_states(moons: &[Moon]) -> [AxisState; 3] {
+    let axis = |coordinate: fn(&Vector3) -> i64| AxisState {
+        positions: moons
+            .iter()
+            .map(|moon| coordinate(&moon.position))
+            .collect(),
+        velocities: moons
+            .iter()
+            .map(|moon| coordinate(&moon.velocity))
+            .collect(),
+    };
+    [axis(|v| v.0), axis(|v| v.1), axis(|v| v.2)]
+}
 
-        if y_cycle == 0
-            && current_moons
-                .iter()
-                .map(|x| x.position.1)
-                .collect::<Vec<i64>>()
-                == ys
-        {
-            y_cycle = counter + 1;
-        }
+#[derive(Debug, PartialEq)]
+struct Periods {
+    /// Periods of the x, y and z axes.
+    axes: [u64; 3],
+    /// Steps until the whole system repeats, `None` when it doesn't fit in `u64`.
+    combined: Option<u64>,
+}
 
-        if z_cycle == 0
-            && current_moons
-                .iter()
-                .map(|x| x.position.2)
-                .collect::<Vec<i64>>()
-                == zs
-        {
-            z_cycle = counter + 1;
-        }
+/// Periods of the axes, each one simulated on its own thread.
+fn find_periods(moons: &[Moon]) -> Periods {
+    let states = axis_states(moons);
+    let axes = std::thread::scope(|scope| {
+        let handles = states
+            .each_ref()
+            .map(|state| scope.spawn(move || state.period()));
+        handles.map(|handle| handle.join().unwrap())
+    });
+    Periods {
+        axes,
+        combined: math::checked_lcm_of(&axes),
     }
-    lcm(lcm(x_cycle, y_cycle), z_cycle)
 }
 
+fn find_cycle(moons: &[Moon]) -> u64 {
+    find_periods(moons).combined.expect("period overflows u64")
+}
+
+/// # Panics
+#[must_use]
 pub fn part2(input: &str) -> u64 {
     let moons: Vec<Moon> = input.lines().map(parse_row).collect();
     find_cycle(&moons)
@@ -253,7 +263,7 @@ mod tests {
 
     #[test]
     fn calculate_gravity_for_moon_test() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(|x| parse_row(x)).collect();
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
         let expected = Moon {
             position: Vector3(-1, 0, 2),
             velocity: Vector3(3, -1, -1),
@@ -263,7 +273,7 @@ mod tests {
 
     #[test]
     fn calculate_gravity_test() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(|x| parse_row(x)).collect();
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
         let expected = vec![
             Moon {
                 position: Vector3(-1, 0, 2),
@@ -287,41 +297,80 @@ mod tests {
 
     #[test]
     fn test_case_1() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(|x| parse_row(x)).collect();
-        assert_eq!(179, energy_after_n_steps(moons, 10))
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
+        assert_eq!(179, energy_after_n_steps(moons, 10));
     }
 
     #[test]
     fn test_case_2() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT2.lines().map(|x| parse_row(x)).collect();
-        assert_eq!(1940, energy_after_n_steps(moons, 100))
+        let moons: Vec<Moon> = TEST_CASE_INPUT2.lines().map(parse_row).collect();
+        assert_eq!(1940, energy_after_n_steps(moons, 100));
     }
 
     #[test]
     fn find_cycle_test1() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(|x| parse_row(x)).collect();
-        assert_eq!(2772, find_cycle(&moons))
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
+        assert_eq!(2772, find_cycle(&moons));
     }
 
     #[test]
     fn find_cycle_test2() {
-        let moons: Vec<Moon> = TEST_CASE_INPUT2.lines().map(|x| parse_row(x)).collect();
-        assert_eq!(4686774924, find_cycle(&moons))
+        let moons: Vec<Moon> = TEST_CASE_INPUT2.lines().map(parse_row).collect();
+        assert_eq!(4_686_774_924, find_cycle(&moons));
     }
 
     #[test]
-    fn test_gcd() {
+    fn axis_state_matches_full_simulation() {
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
+        let mut states = axis_states(&moons);
+        let mut current_moons = moons;
+        for _ in 0..50 {
+            current_moons = calculate_gravity(&current_moons);
+            for moon in &mut current_moons {
+                moon.move_one_timestep();
+            }
+            for state in &mut states {
+                state.step();
+            }
+            assert_eq!(axis_states(&current_moons), states);
+        }
+    }
+
+    #[test]
+    fn periods_test() {
+        let moons: Vec<Moon> = TEST_CASE_INPUT1.lines().map(parse_row).collect();
+        assert_eq!(
+            Periods {
+                axes: [18, 28, 44],
+                combined: Some(2772)
+            },
+            find_periods(&moons)
+        );
+        let moons: Vec<Moon> = TEST_CASE_INPUT2.lines().map(parse_row).collect();
         assert_eq!(
-            2 * 3 * 7 * 5 * 5,
-            gcd(
-                2 * 5 * 7 * 5 * 3 * 11 * 17,
-                2 * 3 * 7 * 5 * 5 * 8 * 7 * 3 * 5 * 7
-            )
-        )
+            Periods {
+                axes: [2028, 5898, 4702],
+                combined: Some(4_686_774_924)
+            },
+            find_periods(&moons)
+        );
     }
 
     #[test]
-    fn test_lcm() {
-        assert_eq!(2 * 3 * 7 * 5 * 5, lcm(2 * 3 * 5, 7 * 5 * 5 * 3))
+    fn periods_with_other_moon_counts() {
+        // A single moon never moves, two moons oscillate around each other
+        let moons: Vec<Moon> = "<x=1, y=2, z=3>".lines().map(parse_row).collect();
+        assert_eq!([1, 1, 1], find_periods(&moons).axes);
+        let moons: Vec<Moon> = "<x=0, y=0, z=0>\n<x=3, y=0, z=1>"
+            .lines()
+            .map(parse_row)
+            .collect();
+        let periods = find_periods(&moons);
+        assert_eq!(1, periods.axes[1]);
+        let mut state = axis_states(&moons)[0].clone();
+        for _ in 0..periods.axes[0] {
+            state.step();
+        }
+        assert_eq!(axis_states(&moons)[0], state);
     }
 }
@@ -1,7 +1,5 @@
-use std::{
-    collections::{HashMap, HashSet},
-    ops::BitAnd,
-};
+use crate::assignment::{self, AssignmentError};
+use std::collections::{HashMap, HashSet};
 
 fn get_allergens(foods: &[Food]) -> HashSet<String> {
     foods.iter().flat_map(|f| f.allergens.clone()).collect()
@@ -13,93 +11,93 @@ fn create_alergen_map(
 ) -> HashMap<String, HashSet<String>> {
     let mut result = HashMap::new();
     for allergen in allergens {
-        let mut possible_foods = HashSet::new();
+        // `None` until the first food listing the allergen, so an empty intersection stays empty
+        let mut possible_foods: Option<HashSet<String>> = None;
         for food in foods {
             if food.allergens.contains(allergen) {
-                if possible_foods.is_empty() {
-                    possible_foods = food.ingredients.clone();
-                } else {
-                    possible_foods = possible_foods.bitand(&food.ingredients);
-                }
+                possible_foods = Some(match possible_foods {
+                    None => food.ingredients.clone(),
+                    Some(possible) => &possible & &food.ingredients,
+                });
             }
         }
-        result.insert(allergen.clone(), possible_foods.into_iter().collect());
+        result.insert(allergen.clone(), possible_foods.unwrap_or_default());
     }
     result
 }
 
-fn reduce_allergen_map(
-    allergen_map: &mut HashMap<String, HashSet<String>>,
-) -> HashMap<String, HashSet<String>> {
-    let mut fixed: Vec<String> = Vec::new();
+fn parse_foods(input: &str) -> Vec<Food> {
+    input.lines().map(parse_row).collect()
+}
 
-    while allergen_map
-        .iter()
-        .any(|(k, v)| v.len() == 1 && !fixed.contains(k))
-    {
-        let (k, v) = allergen_map
-            .iter()
-            .find(|(k, v)| v.len() == 1 && !fixed.contains(k))
-            .unwrap();
-
-        let mut modifications = Vec::new();
-
-        for (key, value) in &*allergen_map {
-            if key != k && value.contains(v.iter().next().unwrap()) {
-                modifications.push((key.clone(), v.clone()));
-            }
-        }
+/// Allergen to the ingredient containing it.
+fn resolve_allergens(
+    foods: &[Food],
+) -> Result<HashMap<String, String>, AssignmentError<String, String>> {
+    let allergens = get_allergens(foods);
+    assignment::unique_assignment(&create_alergen_map(foods, &allergens))
+}
 
-        fixed.push(k.clone());
+/// Ingredient to the allergen it contains, for every ingredient containing one.
+///
+/// # Errors
+/// When the foods don't determine a single ingredient for each allergen.
+pub fn ingredient_allergens(
+    input: &str,
+) -> Result<HashMap<String, String>, AssignmentError<String, String>> {
+    let allergen_map = resolve_allergens(&parse_foods(input))?;
+    Ok(allergen_map
+        .into_iter()
+        .map(|(allergen, ingredient)| (ingredient, allergen))
+        .collect())
+}
 
-        for (key, modification) in modifications {
-            allergen_map
-                .get_mut(&key)
-                .unwrap()
-                .retain(|elem| elem != modification.iter().next().unwrap());
-        }
+/// Ingredients which can't contain any of the allergens, whether or not the allergens can be
+/// resolved.
+///
+/// # Errors
+/// `NoAssignment` when the foods contradict each other, i.e. no ingredient is in every food
+/// listing some allergen. Nothing is provably safe then.
+pub fn safe_ingredients(input: &str) -> Result<HashSet<String>, AssignmentError<String, String>> {
+    let foods = parse_foods(input);
+    let allergen_map = create_alergen_map(&foods, &get_allergens(&foods));
+    if allergen_map.values().any(HashSet::is_empty) {
+        return Err(AssignmentError::NoAssignment);
     }
-
-    allergen_map.clone()
+    let unsafe_ingredients: HashSet<&String> = allergen_map.values().flatten().collect();
+    Ok(foods
+        .iter()
+        .flat_map(|food| &food.ingredients)
+        .filter(|ingredient| !unsafe_ingredients.contains(ingredient))
+        .cloned()
+        .collect())
 }
 
 /// # Panics
 #[must_use]
-#[allow(clippy::implicit_hasher)]
-pub fn create_answer_for_part_2(reduced_map: &HashMap<String, HashSet<String>>) -> String {
-    let mut pairs: Vec<(String, String)> = reduced_map
+pub fn part1(input: &str) -> String {
+    let safe = safe_ingredients(input).unwrap();
+    let result: usize = parse_foods(input)
         .iter()
-        .map(|(k, v)| (k.clone(), v.iter().next().unwrap().clone()))
-        .collect();
-    pairs.sort_by(|(k, _), (k2, _)| k.cmp(k2));
-    let string_vec: Vec<String> = pairs.into_iter().map(|(_k, v)| v).collect();
-    string_vec.join(",")
+        .map(|food| food.ingredients.intersection(&safe).count())
+        .sum();
+    format!("{result}")
 }
 
 /// # Panics
-pub fn part1_and_2(input: &str) -> (usize, String) {
-    let foods: Vec<Food> = input.lines().map(parse_row).collect();
-    let allergens = get_allergens(&foods);
-    let mut allergen_map = create_alergen_map(&foods, &allergens);
-
-    let reduced_map = reduce_allergen_map(&mut allergen_map);
-    let fixed_allergens: Vec<String> = reduced_map
-        .values()
-        .map(|v| v.iter().next().unwrap().clone())
+#[must_use]
+pub fn part2(input: &str) -> String {
+    let mut pairs: Vec<(String, String)> = ingredient_allergens(input)
+        .unwrap()
+        .into_iter()
+        .map(|(ingredient, allergen)| (allergen, ingredient))
         .collect();
-
-    let mut result = 0;
-
-    for food in foods {
-        result += food
-            .ingredients
-            .iter()
-            .filter(|i| !fixed_allergens.contains(i))
-            .count();
-    }
-    let part2 = create_answer_for_part_2(&reduced_map);
-
-    (result, part2)
+    pairs.sort();
+    let ingredients: Vec<String> = pairs
+        .into_iter()
+        .map(|(_, ingredient)| ingredient)
+        .collect();
+    ingredients.join(",")
 }
 
 #[derive(Debug, PartialEq)]
@@ -145,16 +143,13 @@ mod tests {
     fn parse_row_test() {
         let input = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)";
         let expected = Food {
-            ingredients: HashSet::from_iter(
-                vec![
-                    "mxmxvkd".to_owned(),
-                    "kfcds".to_owned(),
-                    "sqjhc".to_owned(),
-                    "nhms".to_owned(),
-                ]
-                .into_iter(),
-            ),
-            allergens: HashSet::from_iter(vec!["dairy".to_owned(), "fish".to_owned()].into_iter()),
+            ingredients: HashSet::from_iter(vec![
+                "mxmxvkd".to_owned(),
+                "kfcds".to_owned(),
+                "sqjhc".to_owned(),
+                "nhms".to_owned(),
+            ]),
+            allergens: HashSet::from_iter(vec!["dairy".to_owned(), "fish".to_owned()]),
         };
         assert_eq!(expected, parse_row(input));
     }
@@ -178,7 +173,7 @@ mod tests {
         let mut expected = HashMap::new();
         expected.insert(
             "fish".to_owned(),
-            HashSet::from_iter(vec![String::from("mxmxvkd"), String::from("sqjhc")].into_iter()),
+            HashSet::from_iter(vec![String::from("mxmxvkd"), String::from("sqjhc")]),
         );
         expected.insert(
             "dairy".to_owned(),
@@ -186,43 +181,103 @@ mod tests {
         );
         expected.insert(
             "soy".to_owned(),
-            HashSet::from_iter(vec![String::from("sqjhc"), String::from("fvjkl")].into_iter()),
+            HashSet::from_iter(vec![String::from("sqjhc"), String::from("fvjkl")]),
         );
         assert_eq!(expected, allergen_map);
     }
 
     #[test]
-    fn reduce_allergen_map_test() {
+    fn resolve_allergens_test() {
         let foods: Vec<Food> = TEST_CASE_INPUT.lines().map(parse_row).collect();
-        let allergens = get_allergens(&foods);
-        let mut allergen_map = create_alergen_map(&foods, &allergens);
-        let reduced_map = reduce_allergen_map(&mut allergen_map);
         let mut expected = HashMap::new();
-        expected.insert(
-            "fish".to_owned(),
-            HashSet::from_iter(vec![String::from("sqjhc")].into_iter()),
+        expected.insert("fish".to_owned(), String::from("sqjhc"));
+        expected.insert("dairy".to_owned(), String::from("mxmxvkd"));
+        expected.insert("soy".to_owned(), String::from("fvjkl"));
+        assert_eq!(Ok(expected), resolve_allergens(&foods));
+    }
+
+    #[test]
+    fn resolve_allergens_reports_ambiguity() {
+        let foods: Vec<Food> = "a b (contains dairy, fish)"
+            .lines()
+            .map(parse_row)
+            .collect();
+        assert!(matches!(
+            resolve_allergens(&foods),
+            Err(AssignmentError::Ambiguous(_, _))
+        ));
+        let foods: Vec<Food> = "a (contains dairy)\nb (contains dairy, fish)"
+            .lines()
+            .map(parse_row)
+            .collect();
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            resolve_allergens(&foods)
         );
-        expected.insert(
-            "dairy".to_owned(),
-            HashSet::from_iter(vec![String::from("mxmxvkd")]),
+    }
+
+    #[test]
+    fn resolve_allergens_contradictory_foods() {
+        // No ingredient is in all three foods, so nothing can contain dairy
+        let foods: Vec<Food> = "a (contains dairy)\nb (contains dairy)\nc (contains dairy)"
+            .lines()
+            .map(parse_row)
+            .collect();
+        let allergen_map = create_alergen_map(&foods, &get_allergens(&foods));
+        assert_eq!(
+            HashMap::from([(String::from("dairy"), HashSet::new())]),
+            allergen_map
         );
-        expected.insert(
-            "soy".to_owned(),
-            HashSet::from_iter(vec![String::from("fvjkl")].into_iter()),
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            resolve_allergens(&foods)
         );
-        assert_eq!(expected, reduced_map);
     }
 
     #[test]
-    fn test_case_part1() {
-        assert_eq!(5, part1_and_2(TEST_CASE_INPUT).0);
+    fn ingredient_allergens_test() {
+        let expected = HashMap::from([
+            (String::from("mxmxvkd"), String::from("dairy")),
+            (String::from("sqjhc"), String::from("fish")),
+            (String::from("fvjkl"), String::from("soy")),
+        ]);
+        assert_eq!(Ok(expected), ingredient_allergens(TEST_CASE_INPUT));
     }
 
     #[test]
-    fn part2_answer_test() {
+    fn safe_ingredients_test() {
+        let expected: HashSet<String> = ["kfcds", "nhms", "sbzzf", "trh"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(Ok(expected), safe_ingredients(TEST_CASE_INPUT));
+
+        // Nothing is resolved here, yet `c` can't contain dairy
+        let input = "a b c (contains dairy)\na b (contains dairy)";
+        assert!(ingredient_allergens(input).is_err());
         assert_eq!(
-            "mxmxvkd,sqjhc,fvjkl".to_owned(),
-            part1_and_2(TEST_CASE_INPUT).1
+            Ok(HashSet::from([String::from("c")])),
+            safe_ingredients(input)
         );
     }
+
+    #[test]
+    fn contradictory_foods() {
+        let input = "a (contains dairy)\nb (contains dairy)\nc (contains dairy)";
+        assert_eq!(
+            Err(AssignmentError::NoAssignment),
+            ingredient_allergens(input)
+        );
+        assert_eq!(Err(AssignmentError::NoAssignment), safe_ingredients(input));
+    }
+
+    #[test]
+    fn test_case_part1() {
+        assert_eq!("5", part1(TEST_CASE_INPUT));
+    }
+
+    #[test]
+    fn test_case_part2() {
+        assert_eq!("mxmxvkd,sqjhc,fvjkl", part2(TEST_CASE_INPUT));
+    }
 }
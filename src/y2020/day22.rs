@@ -18,15 +18,89 @@ enum RoundResult {
     Player2Won,
 }
 
+/// What happens to the cards when both players play the same value. Puzzle decks never hold
+/// duplicates, so this only matters for other inputs.
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum TieRule {
+    Player1Takes,
+    Player2Takes,
+    Discard,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Rules {
+    tie: TieRule,
+    /// Player 1 wins any sub-game in which they hold the highest card. With distinct cards it can
+    /// never leave their deck, so the game ends by player 2 running out of cards or by a repeated
+    /// state. Duplicates break that argument, so the shortcut is skipped for sub-games holding
+    /// any card twice.
+    highest_card_shortcut: bool,
+}
+
+const DEFAULT_RULES: Rules = Rules {
+    tie: TieRule::Player1Takes,
+    highest_card_shortcut: false,
+};
+
+/// `None` when the cards are discarded.
+fn round_winner(player1_card: u8, player2_card: u8, tie: TieRule) -> Option<RoundResult> {
+    match player1_card.cmp(&player2_card) {
+        std::cmp::Ordering::Less => Some(RoundResult::Player2Won),
+        std::cmp::Ordering::Greater => Some(RoundResult::Player1Won),
+        std::cmp::Ordering::Equal => match tie {
+            TieRule::Player1Takes => Some(RoundResult::Player1Won),
+            TieRule::Player2Takes => Some(RoundResult::Player2Won),
+            TieRule::Discard => None,
+        },
+    }
+}
+
+fn collect_cards(
+    player1: &mut Player,
+    player2: &mut Player,
+    player1_card: u8,
+    player2_card: u8,
+    winner: Option<RoundResult>,
+) {
+    match winner {
+        Some(RoundResult::Player1Won) => {
+            player1.deck.push_back(player1_card);
+            player1.deck.push_back(player2_card);
+        }
+        Some(RoundResult::Player2Won) => {
+            player2.deck.push_back(player2_card);
+            player2.deck.push_back(player1_card);
+        }
+        _ => {}
+    }
+}
+
+fn calculate_score(deck: &VecDeque<u8>) -> usize {
+    let length = deck.len();
+    deck.iter()
+        .enumerate()
+        .fold(0, |acc, (i, x)| acc + (length - i) * (*x as usize))
+}
+
 #[derive(Debug, PartialEq)]
 struct Game {
     player1: Player,
     player2: Player,
+    rules: Rules,
 }
 
 impl Game {
     fn new(player1: Player, player2: Player) -> Self {
-        Game { player1, player2 }
+        Game {
+            player1,
+            player2,
+            rules: DEFAULT_RULES,
+        }
+    }
+
+    fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
     }
 
     fn play_round(&mut self) -> RoundResult {
@@ -41,24 +115,21 @@ impl Game {
         let player1_card = self.player1.deck.pop_front().unwrap();
         let player2_card = self.player2.deck.pop_front().unwrap();
 
-        match player1_card.cmp(&player2_card) {
-            std::cmp::Ordering::Less => {
-                self.player2.deck.push_back(player2_card);
-                self.player2.deck.push_back(player1_card);
-            }
-            std::cmp::Ordering::Greater => {
-                self.player1.deck.push_back(player1_card);
-                self.player1.deck.push_back(player2_card);
-            }
-            std::cmp::Ordering::Equal => todo!(),
-        };
+        let winner = round_winner(player1_card, player2_card, self.rules.tie);
+        collect_cards(
+            &mut self.player1,
+            &mut self.player2,
+            player1_card,
+            player2_card,
+            winner,
+        );
         RoundResult::StillPlaying
     }
 
     fn get_end_result(&self, result: RoundResult) -> usize {
         match result {
-            RoundResult::Player1Won => Self::calculate_score(&self.player1.deck),
-            RoundResult::Player2Won => Self::calculate_score(&self.player2.deck),
+            RoundResult::Player1Won => calculate_score(&self.player1.deck),
+            RoundResult::Player2Won => calculate_score(&self.player2.deck),
             RoundResult::StillPlaying => {
                 unreachable!("Impossible state");
             }
@@ -75,10 +146,7 @@ impl Game {
     }
 
     fn calculate_score(deck: &VecDeque<u8>) -> usize {
-        let length = deck.len();
-        deck.iter()
-            .enumerate()
-            .fold(0, |acc, (i, x)| acc + (length - i) * (*x as usize))
+        calculate_score(deck)
     }
 }
 
@@ -99,16 +167,130 @@ fn parse_game_data(input: &str) -> Game {
     Game::new(player1, player2)
 }
 
-#[must_use] pub fn part1(input: &str) -> usize {
+#[must_use]
+pub fn part1(input: &str) -> usize {
     let mut game = parse_game_data(input);
     game.find_final_score()
 }
 
+#[derive(Debug, PartialEq, Clone)]
+enum LogEntry {
+    GameStart {
+        game: usize,
+    },
+    /// Decks at the start of the round, the played cards are at their fronts.
+    Round {
+        game: usize,
+        round: usize,
+        player1_deck: Vec<u8>,
+        player2_deck: Vec<u8>,
+    },
+    /// `None` winner when the cards were discarded.
+    RoundEnd {
+        game: usize,
+        round: usize,
+        winner: Option<RoundResult>,
+    },
+    GameEnd {
+        game: usize,
+        winner: RoundResult,
+    },
+}
+
+/// Rounds and sub-games of a recursive game in the order they were played. Games are numbered
+/// from 1 in the order they start.
+#[derive(Debug, PartialEq, Default)]
+struct GameLog {
+    entries: Vec<LogEntry>,
+    games: usize,
+}
+
+fn player_number(winner: RoundResult) -> usize {
+    match winner {
+        RoundResult::Player1Won => 1,
+        RoundResult::Player2Won => 2,
+        RoundResult::StillPlaying => unreachable!("Game should always end"),
+    }
+}
+
+fn join_deck(deck: &[u8]) -> String {
+    deck.iter()
+        .map(u8::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl GameLog {
+    fn sub_games(&self) -> usize {
+        self.games.saturating_sub(1)
+    }
+
+    /// The log in the format of the puzzle description.
+    fn render(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            match entry {
+                LogEntry::GameStart { game } => {
+                    if *game > 1 {
+                        lines.push(String::from(
+                            "Playing a sub-game to determine the winner...",
+                        ));
+                        lines.push(String::new());
+                    }
+                    lines.push(format!("=== Game {game} ==="));
+                    lines.push(String::new());
+                }
+                LogEntry::Round {
+                    game,
+                    round,
+                    player1_deck,
+                    player2_deck,
+                } => {
+                    lines.push(format!("-- Round {round} (Game {game}) --"));
+                    lines.push(format!("Player 1's deck: {}", join_deck(player1_deck)));
+                    lines.push(format!("Player 2's deck: {}", join_deck(player2_deck)));
+                    lines.push(format!("Player 1 plays: {}", player1_deck[0]));
+                    lines.push(format!("Player 2 plays: {}", player2_deck[0]));
+                }
+                LogEntry::RoundEnd {
+                    game,
+                    round,
+                    winner,
+                } => {
+                    match winner {
+                        Some(winner) => lines.push(format!(
+                            "Player {} wins round {round} of game {game}!",
+                            player_number(*winner)
+                        )),
+                        None => lines.push(format!("Round {round} of game {game} is a draw!")),
+                    }
+                    lines.push(String::new());
+                }
+                LogEntry::GameEnd { game, winner } => {
+                    lines.push(format!(
+                        "The winner of game {game} is player {}!",
+                        player_number(*winner)
+                    ));
+                    if let Some(LogEntry::RoundEnd { game: parent, .. }) = self.entries.get(i + 1) {
+                        lines.push(String::new());
+                        lines.push(format!("...anyway, back to game {parent}."));
+                    }
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct RecursiveGame {
     player1: Player,
     player2: Player,
-    state_history: HashSet<(VecDeque<u8>, VecDeque<u8>)>,
+    rules: Rules,
+    /// Decks at the start of every round played so far, see [`RecursiveGame::state_key`].
+    state_history: HashSet<Vec<u8>>,
+    id: usize,
+    rounds: usize,
 }
 
 impl RecursiveGame {
@@ -116,11 +298,43 @@ impl RecursiveGame {
         RecursiveGame {
             player1,
             player2,
+            rules: DEFAULT_RULES,
             state_history: HashSet::new(),
+            id: 1,
+            rounds: 0,
         }
     }
 
+    fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Both decks in one exact key. Cards can take any byte value, so the length of the first
+    /// deck goes in front instead of a separator between them.
+    fn state_key(&self) -> Vec<u8> {
+        let length = u32::try_from(self.player1.deck.len()).unwrap();
+        let mut key = Vec::with_capacity(4 + self.player1.deck.len() + self.player2.deck.len());
+        key.extend(length.to_le_bytes());
+        key.extend(&self.player1.deck);
+        key.extend(&self.player2.deck);
+        key
+    }
+
+    fn has_distinct_cards(&self) -> bool {
+        let mut seen = [false; 256];
+        self.player1
+            .deck
+            .iter()
+            .chain(&self.player2.deck)
+            .all(|card| !std::mem::replace(&mut seen[usize::from(*card)], true))
+    }
+
     fn play_round(&mut self) -> RoundResult {
+        self.play_logged_round(None)
+    }
+
+    fn play_logged_round(&mut self, mut log: Option<&mut GameLog>) -> RoundResult {
         if self.player1.deck.is_empty() {
             return RoundResult::Player2Won;
         }
@@ -129,96 +343,116 @@ impl RecursiveGame {
             return RoundResult::Player1Won;
         }
 
-        if self
-            .state_history
-            .contains(&(self.player1.deck.clone(), self.player2.deck.clone()))
-        {
+        if !self.state_history.insert(self.state_key()) {
             return RoundResult::Player1Won;
         }
 
-        self.state_history
-            .insert((self.player1.deck.clone(), self.player2.deck.clone()));
+        self.rounds += 1;
+        if let Some(log) = log.as_deref_mut() {
+            log.entries.push(LogEntry::Round {
+                game: self.id,
+                round: self.rounds,
+                player1_deck: self.player1.deck.iter().copied().collect(),
+                player2_deck: self.player2.deck.iter().copied().collect(),
+            });
+        }
 
         let player1_card = self.player1.deck.pop_front().unwrap();
         let player2_card = self.player2.deck.pop_front().unwrap();
 
-        if self.should_recurse(player1_card, player2_card) {
+        let winner = if self.should_recurse(player1_card, player2_card) {
             let mut new_deck1 = self.player1.deck.clone();
             let mut new_deck2 = self.player2.deck.clone();
-            new_deck1.resize(player1_card.into(), 0);
-            new_deck2.resize(player2_card.into(), 0);
-            let mut new_game =
-                RecursiveGame::new(Player { deck: new_deck1 }, Player { deck: new_deck2 });
-            let result = new_game.solve_subgame();
-
-            match result {
-                RoundResult::Player2Won => {
-                    self.player2.deck.push_back(player2_card);
-                    self.player2.deck.push_back(player1_card);
-                }
-                RoundResult::Player1Won => {
-                    self.player1.deck.push_back(player1_card);
-                    self.player1.deck.push_back(player2_card);
-                }
-                RoundResult::StillPlaying => {
-                    unreachable!("Game should always end")
-                }
-            }
-            return RoundResult::StillPlaying;
-        }
-
-        match player1_card.cmp(&player2_card) {
-            std::cmp::Ordering::Less => {
-                self.player2.deck.push_back(player2_card);
-                self.player2.deck.push_back(player1_card);
-            }
-            std::cmp::Ordering::Greater => {
-                self.player1.deck.push_back(player1_card);
-                self.player1.deck.push_back(player2_card);
-            }
-            std::cmp::Ordering::Equal => todo!(),
+            new_deck1.truncate(player1_card.into());
+            new_deck2.truncate(player2_card.into());
+            let mut new_game = RecursiveGame::new(Player::new(new_deck1), Player::new(new_deck2))
+                .with_rules(self.rules);
+            Some(new_game.solve_logged_subgame(log.as_deref_mut()))
+        } else {
+            round_winner(player1_card, player2_card, self.rules.tie)
         };
+
+        if let Some(log) = log {
+            log.entries.push(LogEntry::RoundEnd {
+                game: self.id,
+                round: self.rounds,
+                winner,
+            });
+        }
+        collect_cards(
+            &mut self.player1,
+            &mut self.player2,
+            player1_card,
+            player2_card,
+            winner,
+        );
         RoundResult::StillPlaying
     }
 
     fn get_end_result(&self, result: RoundResult) -> usize {
         match result {
-            RoundResult::Player1Won => Self::calculate_score(&self.player1.deck),
-            RoundResult::Player2Won => Self::calculate_score(&self.player2.deck),
+            RoundResult::Player1Won => calculate_score(&self.player1.deck),
+            RoundResult::Player2Won => calculate_score(&self.player2.deck),
             RoundResult::StillPlaying => {
                 unreachable!("Impossible state");
             }
         }
     }
 
-    fn find_final_score(&mut self) -> usize {
+    /// Plays until the game ends. The shortcut only tells the winner, so it is skipped when the
+    /// final decks matter.
+    fn play_to_end(&mut self, mut log: Option<&mut GameLog>, allow_shortcut: bool) -> RoundResult {
+        if let Some(log) = log.as_deref_mut() {
+            log.games += 1;
+            self.id = log.games;
+            log.entries.push(LogEntry::GameStart { game: self.id });
+        }
+
         let mut game_state = RoundResult::StillPlaying;
+        if allow_shortcut
+            && self.rules.highest_card_shortcut
+            && self.player1.deck.iter().max() > self.player2.deck.iter().max()
+            && self.has_distinct_cards()
+        {
+            game_state = RoundResult::Player1Won;
+        }
 
         while game_state == RoundResult::StillPlaying {
-            game_state = self.play_round();
+            game_state = self.play_logged_round(log.as_deref_mut());
+        }
+
+        if let Some(log) = log {
+            log.entries.push(LogEntry::GameEnd {
+                game: self.id,
+                winner: game_state,
+            });
         }
+        game_state
+    }
+
+    fn find_final_score(&mut self) -> usize {
+        let game_state = self.play_to_end(None, false);
         self.get_end_result(game_state)
     }
 
+    /// Final score together with the log of the whole game.
+    fn logged_final_score(&mut self) -> (usize, GameLog) {
+        let mut log = GameLog::default();
+        let game_state = self.play_to_end(Some(&mut log), false);
+        (self.get_end_result(game_state), log)
+    }
+
     fn should_recurse(&self, player1_card: u8, player2_card: u8) -> bool {
         player1_card as usize <= self.player1.deck.len()
             && player2_card as usize <= self.player2.deck.len()
     }
 
     fn solve_subgame(&mut self) -> RoundResult {
-        let mut game_state = RoundResult::StillPlaying;
-
-        while game_state == RoundResult::StillPlaying {
-            game_state = self.play_round();
-        }
-        game_state
+        self.solve_logged_subgame(None)
     }
 
-    fn calculate_score(deck: &VecDeque<u8>) -> usize {
-        let length = deck.len();
-        deck.iter()
-            .enumerate()
-            .fold(0, |acc, (i, x)| acc + (length - i) * (*x as usize))
+    fn solve_logged_subgame(&mut self, log: Option<&mut GameLog>) -> RoundResult {
+        self.play_to_end(log, true)
     }
 }
 
@@ -230,8 +464,12 @@ fn parse_recursive_game_data(input: &str) -> RecursiveGame {
     RecursiveGame::new(player1, player2)
 }
 
-#[must_use] pub fn part2(input: &str) -> usize {
-    let mut game = parse_recursive_game_data(input);
+#[must_use]
+pub fn part2(input: &str) -> usize {
+    let mut game = parse_recursive_game_data(input).with_rules(Rules {
+        highest_card_shortcut: true,
+        ..DEFAULT_RULES
+    });
     game.find_final_score()
 }
 
@@ -383,7 +621,7 @@ mod tests {
                 deck: VecDeque::from([2, 1, 1]),
             },
         );
-        assert!(game.should_recurse(2, 3))
+        assert!(game.should_recurse(2, 3));
     }
 
     #[test]
@@ -396,7 +634,7 @@ mod tests {
                 deck: VecDeque::from([2, 1]),
             },
         );
-        assert!(!game.should_recurse(3, 3))
+        assert!(!game.should_recurse(3, 3));
     }
 
     #[test]
@@ -405,4 +643,205 @@ mod tests {
         let result = game.find_final_score();
         assert_eq!(291, result);
     }
+
+    #[test]
+    fn tie_rules() {
+        let decks = || {
+            (
+                Player::new(VecDeque::from([3, 1])),
+                Player::new(VecDeque::from([3, 2])),
+            )
+        };
+        let expected = [
+            (TieRule::Player1Takes, vec![1, 3, 3], vec![2]),
+            (TieRule::Player2Takes, vec![1], vec![2, 3, 3]),
+            (TieRule::Discard, vec![1], vec![2]),
+        ];
+        for (tie, deck1, deck2) in expected {
+            let rules = Rules {
+                tie,
+                ..DEFAULT_RULES
+            };
+            let (player1, player2) = decks();
+            let mut game = Game::new(player1, player2).with_rules(rules);
+            assert_eq!(RoundResult::StillPlaying, game.play_round());
+            assert_eq!(VecDeque::from(deck1.clone()), game.player1.deck);
+            assert_eq!(VecDeque::from(deck2.clone()), game.player2.deck);
+
+            let (player1, player2) = decks();
+            let mut game = RecursiveGame::new(player1, player2).with_rules(rules);
+            assert_eq!(RoundResult::StillPlaying, game.play_round());
+            assert_eq!(VecDeque::from(deck1), game.player1.deck);
+            assert_eq!(VecDeque::from(deck2), game.player2.deck);
+        }
+
+        let mut game = parse_game_data("Player 1:\n5\n2\n\nPlayer 2:\n5\n1").with_rules(Rules {
+            tie: TieRule::Discard,
+            ..DEFAULT_RULES
+        });
+        assert_eq!(5, game.find_final_score());
+    }
+
+    #[test]
+    fn state_history_stores_exact_decks() {
+        let mut game = parse_recursive_game_data(TEST_CASE_INPUT);
+        let _ = game.play_round();
+        let _ = game.play_round();
+        assert_eq!(2, game.state_history.len());
+        assert!(game
+            .state_history
+            .contains(&vec![5, 0, 0, 0, 9, 2, 6, 3, 1, 5, 8, 4, 7, 10]));
+
+        // Moving a card across the split gives a different key
+        let game = |deck1: Vec<u8>, deck2: Vec<u8>| {
+            RecursiveGame::new(
+                Player::new(VecDeque::from(deck1)),
+                Player::new(VecDeque::from(deck2)),
+            )
+        };
+        assert_ne!(
+            game(vec![1, 2], vec![3]).state_key(),
+            game(vec![1], vec![2, 3]).state_key()
+        );
+    }
+
+    #[test]
+    fn highest_card_shortcut() {
+        let with_shortcut = Rules {
+            highest_card_shortcut: true,
+            ..DEFAULT_RULES
+        };
+        assert_eq!(291, part2(TEST_CASE_INPUT));
+
+        for input in [TEST_CASE_INPUT, "Player 1:\n2\n9\n1\n\nPlayer 2:\n1\n3\n4"] {
+            let (score, log) = parse_recursive_game_data(input).logged_final_score();
+            let (shortcut_score, shortcut_log) = parse_recursive_game_data(input)
+                .with_rules(with_shortcut)
+                .logged_final_score();
+            assert_eq!(score, shortcut_score);
+            assert_eq!(log.sub_games(), shortcut_log.sub_games());
+            assert!(shortcut_log.entries.len() <= log.entries.len());
+        }
+        let input = "Player 1:\n2\n9\n1\n\nPlayer 2:\n1\n3\n4";
+        let (_, shortcut_log) = parse_recursive_game_data(input)
+            .with_rules(with_shortcut)
+            .logged_final_score();
+        assert_eq!(
+            vec![
+                LogEntry::GameStart { game: 2 },
+                LogEntry::GameEnd {
+                    game: 2,
+                    winner: RoundResult::Player1Won
+                }
+            ],
+            shortcut_log.entries[2..4]
+        );
+
+        for (deck1, deck2) in [
+            (vec![1, 9, 2], vec![3, 4, 5]),
+            (vec![43, 19], vec![2, 29, 14]),
+        ] {
+            let mut game = RecursiveGame::new(
+                Player::new(VecDeque::from(deck1.clone())),
+                Player::new(VecDeque::from(deck2.clone())),
+            );
+            let expected = game.solve_subgame();
+            let mut game = RecursiveGame::new(
+                Player::new(VecDeque::from(deck1)),
+                Player::new(VecDeque::from(deck2)),
+            )
+            .with_rules(with_shortcut);
+            assert_eq!(expected, game.solve_subgame());
+        }
+    }
+
+    #[test]
+    fn highest_card_shortcut_with_duplicates() {
+        // Player 1 holds the highest card but loses when the sub-game is played out
+        let decks = [
+            (vec![1, 4, 1, 1, 2], vec![1, 3, 2, 2, 3, 2]),
+            (vec![3, 1, 3], vec![2, 2]),
+        ];
+        for tie in [
+            TieRule::Player1Takes,
+            TieRule::Player2Takes,
+            TieRule::Discard,
+        ] {
+            for (deck1, deck2) in &decks {
+                let game = |highest_card_shortcut| {
+                    RecursiveGame::new(
+                        Player::new(VecDeque::from(deck1.clone())),
+                        Player::new(VecDeque::from(deck2.clone())),
+                    )
+                    .with_rules(Rules {
+                        tie,
+                        highest_card_shortcut,
+                    })
+                };
+                assert_eq!(
+                    game(false).solve_subgame(),
+                    game(true).solve_subgame(),
+                    "{tie:?} {deck1:?} {deck2:?}"
+                );
+            }
+        }
+        let mut game = RecursiveGame::new(
+            Player::new(VecDeque::from([1, 4, 1, 1, 2])),
+            Player::new(VecDeque::from([1, 3, 2, 2, 3, 2])),
+        )
+        .with_rules(Rules {
+            tie: TieRule::Player1Takes,
+            highest_card_shortcut: true,
+        });
+        assert_eq!(RoundResult::Player2Won, game.solve_subgame());
+    }
+
+    #[test]
+    fn game_log() {
+        let (score, log) = parse_recursive_game_data(TEST_CASE_INPUT).logged_final_score();
+        assert_eq!(291, score);
+        assert_eq!(4, log.sub_games());
+        let rounds = log
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry, LogEntry::Round { game: 1, .. }))
+            .count();
+        assert_eq!(17, rounds);
+
+        let rendered = log.render();
+        assert!(rendered.starts_with(
+            "=== Game 1 ===
+
+-- Round 1 (Game 1) --
+Player 1's deck: 9, 2, 6, 3, 1
+Player 2's deck: 5, 8, 4, 7, 10
+Player 1 plays: 9
+Player 2 plays: 5
+Player 1 wins round 1 of game 1!
+"
+        ));
+        assert!(rendered.contains(
+            "-- Round 9 (Game 1) --
+Player 1's deck: 4, 9, 8, 5, 2
+Player 2's deck: 3, 10, 1, 7, 6
+Player 1 plays: 4
+Player 2 plays: 3
+Playing a sub-game to determine the winner...
+
+=== Game 2 ===
+
+-- Round 1 (Game 2) --
+Player 1's deck: 9, 8, 5, 2
+Player 2's deck: 10, 1, 7
+"
+        ));
+        assert!(rendered.contains(
+            "The winner of game 2 is player 2!
+
+...anyway, back to game 1.
+Player 2 wins round 9 of game 1!
+"
+        ));
+        assert!(rendered.ends_with("The winner of game 1 is player 2!"));
+    }
 }
@@ -156,6 +156,6 @@ mod tests {
     fn test_case_part1() {
         let equations: Vec<Equation> = TEST_DATA.lines().map(Equation::from).collect();
         let mut cache = HashMap::new();
-        assert_eq!(152, eval_equation("root", &equations, &mut cache))
+        assert_eq!(152, eval_equation("root", &equations, &mut cache));
     }
 }
@@ -1,3 +1,6 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+
 fn get_calibration_value(s: &str) -> usize {
     let mut first = None;
     let mut last = None;
@@ -14,54 +17,208 @@ fn get_calibration_value(s: &str) -> usize {
         .unwrap()
 }
 
-#[must_use] pub fn part1(input: &str) -> String {
+#[must_use]
+pub fn part1(input: &str) -> String {
     let result: usize = input.lines().map(|l| get_calibration_value(l.trim())).sum();
     format!("{result}")
 }
 
-fn get_calibration_value2(s: &str, digit_map: &[(&str, char)]) -> usize {
-    let mut first = None;
-    let mut last = None;
-    for (i, c) in s.chars().enumerate() {
-        if let Some(x) = starts_with_digit(&s[i..], digit_map) {
-            last = Some(x.1);
-            if first.is_none() {
-                first = Some(x.1);
+const ENGLISH: [(&str, char); 9] = [
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+];
+
+/// Aho-Corasick automaton over bytes. State 0 is the root.
+#[derive(Debug)]
+struct Automaton {
+    transitions: Vec<HashMap<u8, usize>>,
+    fail: Vec<usize>,
+    /// Patterns ending in each state, those reached through failure links included.
+    outputs: Vec<Vec<usize>>,
+}
+
+impl Automaton {
+    fn new<'a>(patterns: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut automaton = Automaton {
+            transitions: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![Vec::new()],
+        };
+        for (pattern_id, pattern) in patterns.into_iter().enumerate() {
+            let mut state = 0;
+            for byte in pattern {
+                if let Some(next) = automaton.transitions[state].get(byte) {
+                    state = *next;
+                    continue;
+                }
+                let next = automaton.transitions.len();
+                automaton.transitions.push(HashMap::new());
+                automaton.fail.push(0);
+                automaton.outputs.push(Vec::new());
+                automaton.transitions[state].insert(*byte, next);
+                state = next;
             }
-        } else if c.is_ascii_digit() {
-            last = Some(c);
-            if first.is_none() {
-                first = Some(c);
+            automaton.outputs[state].push(pattern_id);
+        }
+
+        // Breadth first, so the failure target of every state is done before the state itself
+        let mut queue: VecDeque<usize> = automaton.transitions[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let children: Vec<(u8, usize)> = automaton.transitions[state]
+                .iter()
+                .map(|(byte, next)| (*byte, *next))
+                .collect();
+            for (byte, next) in children {
+                let fail = if state == 0 {
+                    0
+                } else {
+                    automaton.step(automaton.fail[state], byte)
+                };
+                automaton.fail[next] = fail;
+                let inherited = automaton.outputs[fail].clone();
+                automaton.outputs[next].extend(inherited);
+                queue.push_back(next);
             }
         }
+        automaton
+    }
+
+    fn step(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(next) = self.transitions[state].get(&byte) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
     }
-    format!("{}{}", first.unwrap(), last.unwrap())
-        .parse()
-        .unwrap()
 }
 
-fn starts_with_digit<'a>(
-    s: &'a str,
-    digit_map: &'a [(&'a str, char)],
-) -> Option<&'a (&'a str, char)> {
-    digit_map.iter().find(|(k, _)| s.starts_with(k))
+/// Digit written as a number or a word of the vocabulary, with its byte range in the line.
+#[derive(Debug, PartialEq)]
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    text: &'a str,
+    digit: char,
+}
+
+/// Finds digits and spelled digits of a vocabulary in lines. Words may overlap, like "oneight",
+/// or contain each other.
+#[derive(Debug)]
+struct DigitScanner {
+    words: Vec<(String, char)>,
+    forward: Automaton,
+    /// Automaton of the reversed words, for scanning from the end of a line.
+    backward: Automaton,
+    longest: usize,
+}
+
+impl DigitScanner {
+    /// Scanner for the words of `vocabulary` next to the digits `0` to `9`.
+    fn new(vocabulary: &[(&str, char)]) -> Self {
+        let words: Vec<(String, char)> = ('0'..='9')
+            .map(|digit| (digit.to_string(), digit))
+            .chain(
+                vocabulary
+                    .iter()
+                    .map(|(word, digit)| ((*word).to_owned(), *digit)),
+            )
+            .filter(|(word, _)| !word.is_empty())
+            .collect();
+        let reversed: Vec<Vec<u8>> = words
+            .iter()
+            .map(|(word, _)| word.bytes().rev().collect())
+            .collect();
+        DigitScanner {
+            forward: Automaton::new(words.iter().map(|(word, _)| word.as_bytes())),
+            backward: Automaton::new(reversed.iter().map(Vec::as_slice)),
+            longest: words
+                .iter()
+                .map(|(word, _)| word.len())
+                .max()
+                .unwrap_or_default(),
+            words,
+        }
+    }
+
+    /// All matches in the line, overlapping ones included, ordered by position.
+    fn tokens<'a>(&self, line: &'a str) -> Vec<Token<'a>> {
+        let mut result = Vec::new();
+        let mut state = 0;
+        for (i, byte) in line.bytes().enumerate() {
+            state = self.forward.step(state, byte);
+            for word in &self.forward.outputs[state] {
+                let start = i + 1 - self.words[*word].0.len();
+                result.push(Token {
+                    start,
+                    end: i + 1,
+                    text: &line[start..=i],
+                    digit: self.words[*word].1,
+                });
+            }
+        }
+        result.sort_by_key(|token| (token.start, token.end));
+        result
+    }
+
+    /// Digit of the match starting first, the longest one on ties.
+    fn first_digit(&self, line: &str) -> Option<char> {
+        self.earliest_match(&self.forward, line.bytes())
+    }
+
+    /// Digit of the match ending last, the longest one on ties, found from the end of the line.
+    fn last_digit(&self, line: &str) -> Option<char> {
+        self.earliest_match(&self.backward, line.bytes().rev())
+    }
+
+    /// Scanning stops once no later match can start before the best one so far.
+    fn earliest_match(
+        &self,
+        automaton: &Automaton,
+        bytes: impl Iterator<Item = u8>,
+    ) -> Option<char> {
+        let mut best: Option<(usize, Reverse<usize>, char)> = None;
+        let mut state = 0;
+        for (i, byte) in bytes.enumerate() {
+            if best.is_some_and(|(start, ..)| i >= start + self.longest) {
+                break;
+            }
+            state = automaton.step(state, byte);
+            for word in &automaton.outputs[state] {
+                let (text, digit) = &self.words[*word];
+                let candidate = (i + 1 - text.len(), Reverse(text.len()), *digit);
+                if best.is_none_or(|best| candidate < best) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best.map(|(.., digit)| digit)
+    }
+
+    fn calibration_value(&self, line: &str) -> Option<usize> {
+        let first = self.first_digit(line)?.to_digit(10)?;
+        let last = self.last_digit(line)?.to_digit(10)?;
+        Some((first * 10 + last) as usize)
+    }
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
-    let digit_map = [
-        ("one", '1'),
-        ("two", '2'),
-        ("three", '3'),
-        ("four", '4'),
-        ("five", '5'),
-        ("six", '6'),
-        ("seven", '7'),
-        ("eight", '8'),
-        ("nine", '9'),
-    ];
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
+    let scanner = DigitScanner::new(&ENGLISH);
     let result: usize = input
         .lines()
-        .map(|l| get_calibration_value2(l.trim(), &digit_map))
+        .map(|l| scanner.calibration_value(l.trim()).unwrap())
         .sum();
     format!("{result}")
 }
@@ -84,18 +241,6 @@ mod tests {
         zoneight234
         7pqrstsixteen";
 
-    const DIGIT_MAP: [(&str, char); 9] = [
-        ("one", '1'),
-        ("two", '2'),
-        ("three", '3'),
-        ("four", '4'),
-        ("five", '5'),
-        ("six", '6'),
-        ("seven", '7'),
-        ("eight", '8'),
-        ("nine", '9'),
-    ];
-
     #[test]
     fn calibration_value_when_1_digit() {
         let x = "treb7uchet";
@@ -114,33 +259,75 @@ mod tests {
     }
 
     #[test]
-    fn starts_with_spelled_digit() {
-        let x = "one1osidjgosij";
-        assert_eq!(Some(&("one", '1')), starts_with_digit(x, &DIGIT_MAP));
+    fn calibration_value_when_1_digit_v2() {
+        let scanner = DigitScanner::new(&ENGLISH);
+        assert_eq!(Some(77), scanner.calibration_value("treb7uchet"));
     }
 
     #[test]
-    fn starts_with_regular_digit() {
-        let x = "1one1osidjgosij";
-        assert_eq!(None, starts_with_digit(x, &DIGIT_MAP));
+    fn calibration_value_when_many_digits_v2() {
+        let scanner = DigitScanner::new(&ENGLISH);
+        assert_eq!(Some(15), scanner.calibration_value("a1b2c3d4e5f"));
     }
 
     #[test]
-    fn calibration_value_when_1_digit_v2() {
-        let x = "treb7uchet";
-        assert_eq!(77, get_calibration_value2(x, &DIGIT_MAP));
+    fn calibration_value_v2_with_spelled_digit() {
+        let scanner = DigitScanner::new(&ENGLISH);
+        assert_eq!(Some(13), scanner.calibration_value("abcone2threexyz"));
+        assert_eq!(Some(18), scanner.calibration_value("zoneight"));
+        assert_eq!(None, scanner.calibration_value("abc"));
     }
 
     #[test]
-    fn calibration_value_when_many_digits_v2() {
-        let x = "a1b2c3d4e5f";
-        assert_eq!(15, get_calibration_value2(x, &DIGIT_MAP));
+    fn tokens_with_overlaps() {
+        let scanner = DigitScanner::new(&ENGLISH);
+        let token = |start, text: &'static str, digit| Token {
+            start,
+            end: start + text.len(),
+            text,
+            digit,
+        };
+        assert_eq!(
+            vec![
+                token(1, "one", '1'),
+                token(3, "eight", '8'),
+                token(9, "2", '2'),
+            ],
+            scanner.tokens("zoneightx2")
+        );
+        assert_eq!(
+            vec![token(0, "two", '2'), token(2, "one", '1')],
+            scanner.tokens("twone")
+        );
     }
 
     #[test]
-    fn calibration_value_v2_with_spelled_digit() {
-        let x = "abcone2threexyz";
-        assert_eq!(13, get_calibration_value2(x, &DIGIT_MAP));
+    fn other_vocabularies() {
+        let german = [("eins", '1'), ("zwei", '2'), ("drei", '3'), ("vier", '4')];
+        let scanner = DigitScanner::new(&german);
+        assert_eq!(Some(24), scanner.calibration_value("xzweinsvierx"));
+        assert_eq!(Some(31), scanner.calibration_value("dreins"));
+        assert_eq!(None, scanner.calibration_value("one"));
+
+        // Words inside other words: the first match starts earliest and the last one ends latest,
+        // the longer word winning ties
+        let nested = [("abcd", '4'), ("bc", '2'), ("c", '3')];
+        let scanner = DigitScanner::new(&nested);
+        assert_eq!(Some(44), scanner.calibration_value("abcd"));
+        assert_eq!(Some(22), scanner.calibration_value("xbcx"));
+        assert_eq!(
+            vec!['4', '2', '3'],
+            scanner
+                .tokens("abcd")
+                .iter()
+                .map(|token| token.digit)
+                .collect::<Vec<_>>()
+        );
+
+        // Non-ASCII words keep byte positions on character boundaries
+        let scanner = DigitScanner::new(&[("één", '1'), ("twee", '2')]);
+        assert_eq!(Some(12), scanner.calibration_value("xééntweex"));
+        assert_eq!("één", scanner.tokens("xééntweex")[0].text);
     }
 
     #[test]
@@ -42,64 +42,71 @@ impl Galaxy {
         }
     }
 
-    fn find_distances(&self, expansion_rate: usize) -> usize {
-        let number_of_stars = self.stars.len();
-        let mut result = 0;
-
-        for i in 0..number_of_stars {
-            for j in i + 1..number_of_stars {
-                let star1 = self.stars.get(i).unwrap();
-                let star2 = self.stars.get(j).unwrap();
-                result += self.distance(*star1, *star2, expansion_rate);
-            }
-        }
+    /// The galaxy after every empty row and column gained `expansion_rate` extra copies.
+    fn expand(&self, expansion_rate: u64) -> ExpandedGalaxy {
+        // u128 leaves room for any u64 rate
+        let expand = |coordinate: usize, empty: &[usize]| {
+            let empty_before = empty.partition_point(|e| *e < coordinate);
+            coordinate as u128 + empty_before as u128 * u128::from(expansion_rate)
+        };
+        let stars = self
+            .stars
+            .iter()
+            .map(|(x, y)| {
+                (
+                    expand(*x, &self.empty_vertical),
+                    expand(*y, &self.empty_horizontal),
+                )
+            })
+            .collect();
+        ExpandedGalaxy { stars }
+    }
+}
+
+/// Star coordinates after expansion, in the order they were parsed.
+struct ExpandedGalaxy {
+    stars: Vec<(u128, u128)>,
+}
 
-        result
+impl ExpandedGalaxy {
+    /// Sum of the distances between all pairs of stars. Per axis, the sorted coordinate at index
+    /// `i` is the larger one in `i` pairs, which prefix sums count in one pass.
+    fn find_distances(&self) -> u128 {
+        let (mut xs, mut ys): (Vec<u128>, Vec<u128>) = self.stars.iter().copied().unzip();
+        xs.sort_unstable();
+        ys.sort_unstable();
+        axis_distances(&xs) + axis_distances(&ys)
     }
 
-    fn distance(
-        &self,
-        star1: (usize, usize),
-        star2: (usize, usize),
-        expansion_rate: usize,
-    ) -> usize {
-        let (min_x, max_x) = if star1.0 < star2.0 {
-            (star1.0, star2.0)
-        } else {
-            (star2.0, star1.0)
-        };
-        let (min_y, max_y) = if star1.1 < star2.1 {
-            (star1.1, star2.1)
-        } else {
-            (star2.1, star1.1)
-        };
+    /// Distance between the stars with the given indices.
+    fn distance(&self, star1: usize, star2: usize) -> Option<u128> {
+        let (x1, y1) = self.stars.get(star1)?;
+        let (x2, y2) = self.stars.get(star2)?;
+        Some(x1.abs_diff(*x2) + y1.abs_diff(*y2))
+    }
+}
 
-        star1.0.abs_diff(star2.0)
-            + star1.1.abs_diff(star2.1)
-            + self
-                .empty_horizontal
-                .iter()
-                .filter(|&&x| x < max_y && x > min_y)
-                .count()
-                * expansion_rate
-            + self
-                .empty_vertical
-                .iter()
-                .filter(|&&x| x < max_x && x > min_x)
-                .count()
-                * expansion_rate
+fn axis_distances(sorted: &[u128]) -> u128 {
+    let mut prefix = 0;
+    let mut result = 0;
+    for (i, value) in sorted.iter().enumerate() {
+        result += value * i as u128 - prefix;
+        prefix += value;
     }
+    result
 }
 
-#[must_use] pub fn part1(input: &str) -> String {
+#[must_use]
+pub fn part1(input: &str) -> String {
     let galaxy = Galaxy::parse(input);
-    let result = galaxy.find_distances(1);
+    let result = galaxy.expand(1).find_distances();
     format!("{result}")
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
+#[must_use]
+pub fn part2(input: &str) -> String {
     let galaxy = Galaxy::parse(input);
-    let result = galaxy.find_distances(999_999);
+    let result = galaxy.expand(999_999).find_distances();
     format!("{result}")
 }
 
@@ -150,14 +157,49 @@ mod tests {
     #[test]
     fn test_case_part2_1() {
         let galaxy = Galaxy::parse(TEST_DATA);
-        let result = galaxy.find_distances(9);
+        let result = galaxy.expand(9).find_distances();
         assert_eq!(1030, result);
     }
 
     #[test]
     fn test_case_part2_2() {
         let galaxy = Galaxy::parse(TEST_DATA);
-        let result = galaxy.find_distances(99);
+        let result = galaxy.expand(99).find_distances();
         assert_eq!(8410, result);
     }
+
+    #[test]
+    fn distance_by_index() {
+        let galaxy = Galaxy::parse(TEST_DATA).expand(1);
+        assert_eq!(Some(9), galaxy.distance(4, 8));
+        assert_eq!(Some(15), galaxy.distance(0, 6));
+        assert_eq!(Some(17), galaxy.distance(2, 5));
+        assert_eq!(Some(5), galaxy.distance(7, 8));
+        assert_eq!(Some(0), galaxy.distance(3, 3));
+        assert_eq!(None, galaxy.distance(0, 9));
+    }
+
+    #[test]
+    fn matches_pairwise_sum() {
+        let galaxy = Galaxy::parse(TEST_DATA);
+        for expansion_rate in [0, 1, 7, 999_999] {
+            let expanded = galaxy.expand(expansion_rate);
+            let mut expected = 0;
+            for i in 0..expanded.stars.len() {
+                for j in i + 1..expanded.stars.len() {
+                    expected += expanded.distance(i, j).unwrap();
+                }
+            }
+            assert_eq!(expected, expanded.find_distances());
+        }
+    }
+
+    #[test]
+    fn largest_expansion_rate() {
+        let galaxy = Galaxy::parse(TEST_DATA).expand(u64::MAX);
+        let rate = u128::from(u64::MAX);
+        // Three empty columns and one empty row lie between these two
+        assert_eq!(Some(13 + 4 * rate), galaxy.distance(2, 5));
+        assert_eq!(82 * rate + 292, galaxy.find_distances());
+    }
 }
@@ -1,16 +1,13 @@
-use std::collections::HashMap;
-
 #[derive(Debug, PartialEq)]
 struct Row {
-    row: String,
+    springs: String,
     damaged: Vec<usize>,
-    map: HashMap<(usize, usize, usize), usize>,
 }
 
 impl Row {
     fn parse(input: &str) -> Row {
         let mut split = input.split(' ');
-        let row = split.next().unwrap();
+        let springs = split.next().unwrap();
         let damaged: Vec<usize> = split
             .next()
             .unwrap()
@@ -18,230 +15,151 @@ impl Row {
             .map(|x| x.parse::<usize>().unwrap())
             .collect();
         Row {
-            row: String::from(row),
+            springs: String::from(springs),
             damaged,
-            map: HashMap::new(),
         }
     }
 
     fn parse_part2(input: &str) -> Row {
-        let mut split = input.split(' ');
-        let row = split.next().unwrap();
-        let numbers: Vec<usize> = split
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect();
-
-        let mut damaged = Vec::new();
-        for _i in 0..5 {
-            damaged.extend(numbers.clone());
-        }
+        Row::parse(input).unfold(5)
+    }
 
+    /// Repeats springs `factor` times joined with `?` and damaged groups `factor` times.
+    fn unfold(&self, factor: usize) -> Row {
         Row {
-            row: format!("{row}?{row}?{row}?{row}?{row}"),
-            damaged,
-            map: HashMap::new(),
+            springs: vec![self.springs.as_str(); factor].join("?"),
+            damaged: self.damaged.repeat(factor),
         }
     }
 
-    fn count_possibilities(&mut self) -> usize {
-        let length = self.row.len();
-        self.backtrack("", 0, length, 0, 0)
-    }
-
-    fn backtrack(
-        &mut self,
-        current_string: &str,
-        length: usize,
-        real_length: usize,
-        number_of_hashes: usize,
-        blocks_placed: usize,
-    ) -> usize {
-        if self
-            .map
-            .contains_key(&(length, number_of_hashes, blocks_placed))
-        {
-            let value = *self
-                .map
-                .get(&(length, number_of_hashes, blocks_placed))
-                .unwrap();
-            return value;
-        }
+    /// `table[i][j]` is the number of arrangements of `springs[i..]` using `damaged[j..]`.
+    fn arrangements_table(&self) -> Vec<Vec<usize>> {
+        let springs = self.springs.as_bytes();
+        let length = springs.len();
+        let groups = self.damaged.len();
 
-        if length == real_length {
-            if self.is_correct(current_string).0 {
-                return 1;
+        // How many springs starting at given position could be damaged
+        let mut possibly_damaged = vec![0; length + 1];
+        for i in (0..length).rev() {
+            if springs[i] != b'.' {
+                possibly_damaged[i] = possibly_damaged[i + 1] + 1;
             }
-            return 0;
         }
 
-        let current_char = self.row.chars().nth(length).unwrap();
-        match current_char {
-            '#' => {
-                let mut new_string = current_string.to_string();
-                new_string.push('#');
-                self.backtrack(
-                    &new_string,
-                    length + 1,
-                    real_length,
-                    number_of_hashes + 1,
-                    blocks_placed,
-                )
-            }
-            '.' => {
-                let mut new_string = current_string.to_string();
-                new_string.push('.');
-                let result = self.backtrack(
-                    &new_string,
-                    length + 1,
-                    real_length,
-                    number_of_hashes,
-                    blocks_placed,
-                );
-                self.map
-                    .insert((length, number_of_hashes, blocks_placed), result);
-                result
-            }
-
-            '?' => {
-                let mut new_string1 = current_string.to_string();
-                let mut new_string2 = current_string.to_string();
-                new_string1.push('#');
-                new_string2.push('.');
-                let (possible1, blocks_placed1) = self.is_possible_solution(&new_string1);
-                let (possible2, blocks_placed2) = self.is_possible_solution(&new_string2);
-                if possible1 && possible2 {
-                    let result = self.backtrack(
-                        &new_string1,
-                        length + 1,
-                        real_length,
-                        number_of_hashes + 1,
-                        blocks_placed1,
-                    ) + self.backtrack(
-                        &new_string2,
-                        length + 1,
-                        real_length,
-                        number_of_hashes,
-                        blocks_placed2,
-                    );
-                    self.map
-                        .insert((length, number_of_hashes, blocks_placed), result);
-                    result
-                } else if possible1 {
-                    self.backtrack(
-                        &new_string1,
-                        length + 1,
-                        real_length,
-                        number_of_hashes + 1,
-                        blocks_placed1,
-                    )
-                } else if possible2 {
-                    let result = self.backtrack(
-                        &new_string2,
-                        length + 1,
-                        real_length,
-                        number_of_hashes,
-                        blocks_placed2,
-                    );
-                    self.map
-                        .insert((length, number_of_hashes, blocks_placed), result);
-                    result
-                } else {
-                    0
+        let mut table = vec![vec![0; groups + 1]; length + 1];
+        table[length][groups] = 1;
+        for i in (0..length).rev() {
+            for j in 0..=groups {
+                let mut ways = 0;
+                if springs[i] != b'#' {
+                    ways += table[i + 1][j];
                 }
+                if springs[i] != b'.' && j < groups {
+                    let end = i + self.damaged[j];
+                    if possibly_damaged[i] >= self.damaged[j]
+                        && (end == length || springs[end] != b'#')
+                    {
+                        ways += table[(end + 1).min(length)][j + 1];
+                    }
+                }
+                table[i][j] = ways;
             }
-            _ => unreachable!(),
         }
+        table
     }
 
-    fn is_correct(&self, current_string: &str) -> (bool, usize) {
-        let mut blocks_placed = 0;
-        let mut damaged_number = 0;
-        let mut damaged_iter = self.damaged.iter().peekable();
-        for c in current_string.chars() {
-            if c == '.' {
-                if damaged_number > 0 && damaged_iter.peek().is_none() {
-                    return (false, blocks_placed);
-                }
-                if damaged_number > 0 && *damaged_iter.next().unwrap() != damaged_number {
-                    return (false, blocks_placed);
-                }
-                damaged_number = 0;
-                blocks_placed += 1;
-                continue;
-            }
-            damaged_number += 1;
-        }
+    fn count_possibilities(&self) -> usize {
+        self.arrangements_table()[0][0]
+    }
 
-        if damaged_number > 0 && damaged_iter.peek().is_none() {
-            return (false, blocks_placed);
-        }
-        if damaged_number > 0 && *damaged_iter.next().unwrap() != damaged_number {
-            return (false, blocks_placed);
-        }
+    /// Lists up to `limit` concrete arrangements, useful for checking small rows by hand.
+    fn arrangements(&self, limit: usize) -> Vec<String> {
+        let table = self.arrangements_table();
+        let mut result = Vec::new();
+        let mut current = Vec::with_capacity(self.springs.len());
+        self.collect_arrangements(&table, 0, 0, &mut current, &mut result, limit);
+        result
+    }
 
-        if damaged_iter.peek().is_some() {
-            return (false, blocks_placed);
+    fn collect_arrangements(
+        &self,
+        table: &[Vec<usize>],
+        i: usize,
+        j: usize,
+        current: &mut Vec<u8>,
+        result: &mut Vec<String>,
+        limit: usize,
+    ) {
+        let springs = self.springs.as_bytes();
+        if result.len() >= limit || table[i][j] == 0 {
+            return;
+        }
+        if i == springs.len() {
+            result.push(String::from_utf8(current.clone()).unwrap());
+            return;
         }
 
-        blocks_placed += 1;
-
-        (true, blocks_placed)
-    }
-
-    fn is_possible_solution(&self, current_string: &str) -> (bool, usize) {
-        let mut blocks_placed = 0;
-        let mut damaged_number = 0;
-        let mut damaged_iter = self.damaged.iter().peekable();
-        for c in current_string.chars() {
-            if c == '.' {
-                if damaged_number > 0 && damaged_iter.peek().is_none() {
-                    return (false, blocks_placed);
+        if springs[i] != b'.' && j < self.damaged.len() {
+            let end = i + self.damaged[j];
+            if end <= springs.len()
+                && springs[i..end].iter().all(|c| *c != b'.')
+                && (end == springs.len() || springs[end] != b'#')
+            {
+                let depth = current.len();
+                current.extend(std::iter::repeat_n(b'#', self.damaged[j]));
+                if end < springs.len() {
+                    current.push(b'.');
                 }
-                if damaged_number > 0 {
-                    if *damaged_iter.next().unwrap() != damaged_number {
-                        return (false, blocks_placed);
-                    }
-                    blocks_placed += 1;
-                }
-                damaged_number = 0;
-                continue;
+                let next = (end + 1).min(springs.len());
+                self.collect_arrangements(table, next, j + 1, current, result, limit);
+                current.truncate(depth);
             }
-            damaged_number += 1;
-        }
-
-        if damaged_number > 0 && damaged_iter.peek().is_none() {
-            return (false, blocks_placed);
         }
-        if damaged_number > 0 && *damaged_iter.next().unwrap() < damaged_number {
-            return (false, blocks_placed);
+        if springs[i] != b'#' {
+            current.push(b'.');
+            self.collect_arrangements(table, i + 1, j, current, result, limit);
+            current.pop();
         }
+    }
 
-        blocks_placed += 1;
+    /// Whether fully resolved `arrangement` consists of exactly the damaged groups.
+    fn is_correct(&self, arrangement: &str) -> bool {
+        let groups: Vec<usize> = arrangement
+            .split('.')
+            .filter(|group| !group.is_empty())
+            .map(str::len)
+            .collect();
+        groups == self.damaged
+    }
 
-        (true, blocks_placed)
+    fn fits_pattern(&self, arrangement: &str) -> bool {
+        arrangement.len() == self.springs.len()
+            && arrangement
+                .bytes()
+                .zip(self.springs.bytes())
+                .all(|(a, s)| a != b'?' && (s == b'?' || a == s))
     }
 }
 
-#[must_use] pub fn part1(input: &str) -> String {
+#[must_use]
+pub fn part1(input: &str) -> String {
     let rows: Vec<Row> = input.lines().map(|line| Row::parse(line.trim())).collect();
     let mut result = 0;
-    for mut row in rows {
+    for row in rows {
         let possibilities = row.count_possibilities();
         result += possibilities;
     }
     format!("{result}")
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
+#[must_use]
+pub fn part2(input: &str) -> String {
     let rows: Vec<Row> = input
         .lines()
         .map(|line| Row::parse_part2(line.trim()))
         .collect();
     let mut result = 0;
-    for mut row in rows {
+    for row in rows {
         let possibilities = row.count_possibilities();
         result += possibilities;
     }
@@ -263,16 +181,9 @@ mod tests {
     #[test]
     fn parse_test() {
         let input = "????.######..#####. 1,6,5";
-        let row = String::from("????.######..#####.");
+        let springs = String::from("????.######..#####.");
         let damaged = Vec::from([1, 6, 5]);
-        assert_eq!(
-            Row {
-                row,
-                damaged,
-                map: HashMap::new()
-            },
-            Row::parse(input)
-        );
+        assert_eq!(Row { springs, damaged }, Row::parse(input));
     }
 
     #[test]
@@ -293,8 +204,43 @@ mod tests {
         let row = Row::parse(input);
         let test_input1 = "..#...#....###.";
         let test_input2 = ".#...#.....###.";
-        assert!(row.is_correct(test_input1).0);
-        assert!(row.is_correct(test_input2).0);
+        assert!(row.is_correct(test_input1));
+        assert!(row.is_correct(test_input2));
+        assert!(!row.is_correct("..#...#...####."));
+        assert!(!row.is_correct(".#.#.#.....###."));
+    }
+
+    #[test]
+    fn unfold_test() {
+        let row = Row::parse(".# 1").unfold(3);
+        assert_eq!(
+            Row {
+                springs: String::from(".#?.#?.#"),
+                damaged: vec![1, 1, 1]
+            },
+            row
+        );
+        assert_eq!(Row::parse(".# 1"), Row::parse(".# 1").unfold(1));
+    }
+
+    #[test]
+    fn count_possibilities_other_unfold_factors() {
+        let row = Row::parse("????.######..#####. 1,6,5");
+        assert_eq!(4, row.count_possibilities());
+        assert_eq!(20, row.unfold(2).count_possibilities());
+        assert_eq!(100, row.unfold(3).count_possibilities());
+    }
+
+    #[test]
+    fn arrangements_test() {
+        let row = Row::parse("?###???????? 3,2,1");
+        let arrangements = row.arrangements(usize::MAX);
+        assert_eq!(row.count_possibilities(), arrangements.len());
+        assert_eq!(".###.##.#...", arrangements[0]);
+        assert!(arrangements
+            .iter()
+            .all(|a| row.is_correct(a) && row.fits_pattern(a)));
+        assert_eq!(3, row.arrangements(3).len());
     }
 
     #[test]
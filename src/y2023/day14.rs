@@ -89,19 +89,14 @@ impl Grid {
                         }
                     },
                     Some(i) => match last_rock_position {
-                        None => {
+                        Some(j) if j > i => {
+                            rocks_positions.push(j + 1);
+                            last_rock_position = Some(j + 1);
+                        }
+                        _ => {
                             rocks_positions.push(i + 1);
                             last_rock_position = Some(i + 1);
                         }
-                        Some(j) => {
-                            if j > i {
-                                rocks_positions.push(j + 1);
-                                last_rock_position = Some(j + 1);
-                            } else {
-                                rocks_positions.push(i + 1);
-                                last_rock_position = Some(i + 1);
-                            }
-                        }
                     },
                 },
                 '.' => {}
@@ -15,101 +15,162 @@ pub fn part1(input: &str) -> String {
     format!("{result}")
 }
 
-#[derive(Debug, PartialEq)]
-struct Lens {
-    label: String,
-    label_hash: usize,
-    mode: char,
-    focal_length: Option<usize>,
+#[derive(Debug, PartialEq, Clone)]
+enum Step {
+    Insert { label: String, focal: usize },
+    Remove { label: String },
 }
 
-impl Lens {
-    fn parse(input: &str) -> Lens {
-        if input.contains('=') {
-            let mut split = input.split('=');
-            let label = split.next().unwrap().to_owned();
-            let label_hash = hash(&label);
-            let focal_length = Some(split.next().unwrap().parse::<usize>().unwrap());
-            return Lens {
-                label,
-                label_hash,
-                mode: '=',
-                focal_length,
-            };
-        } else if input.contains('-') {
-            let mut split = input.split('-');
-            let label = split.next().unwrap().to_owned();
-            let label_hash = hash(&label);
-            let focal_length = None;
-            return Lens {
-                label,
-                label_hash,
-                mode: '-',
-                focal_length,
-            };
+impl Step {
+    fn parse(input: &str) -> Step {
+        let input = input.trim();
+        if let Some((label, focal)) = input.split_once('=') {
+            Step::Insert {
+                label: label.to_owned(),
+                focal: focal.parse().unwrap(),
+            }
+        } else if let Some(label) = input.strip_suffix('-') {
+            Step::Remove {
+                label: label.to_owned(),
+            }
+        } else {
+            panic!("unknown step {input}");
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            Step::Insert { label, .. } | Step::Remove { label } => label,
         }
-        unreachable!();
     }
 }
 
-fn focusing_power(boxes: HashMap<usize, Vec<Lens>>) -> usize {
-    let mut result = 0;
-    for (b, v) in boxes {
-        for (i, e) in v.iter().enumerate() {
-            result += (b + 1) * (i + 1) * e.focal_length.unwrap();
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Insert { label, focal } => write!(f, "{label}={focal}"),
+            Step::Remove { label } => write!(f, "{label}-"),
         }
     }
-    result
 }
 
-/// # Panics
-#[must_use]
-pub fn part2(input: &str) -> String {
-    let split = input.trim().split(',');
-    let mut lenses: Vec<Lens> = Vec::new();
-    for s in split {
-        lenses.push(Lens::parse(s));
-    }
-
-    let mut boxes: HashMap<usize, Vec<Lens>> = HashMap::new();
-
-    for i in 0..256 {
-        boxes.insert(i, Vec::new());
-    }
-
-    for lens in lenses {
-        if lens.mode == '-'
-            && boxes
-                .get(&lens.label_hash)
-                .unwrap()
-                .iter()
-                .any(|l: &Lens| l.label == lens.label)
-        {
-            boxes
-                .get_mut(&lens.label_hash)
-                .unwrap()
-                .retain(|l| l.label != lens.label);
-        } else if lens.mode == '=' {
-            if boxes
-                .get(&lens.label_hash)
-                .unwrap()
-                .iter()
-                .any(|l: &Lens| l.label == lens.label)
-            {
-                let mut_iter = boxes.get_mut(&lens.label_hash).unwrap().iter_mut();
-
-                for l in mut_iter {
-                    if l.label == lens.label {
-                        l.focal_length = lens.focal_length;
-                    }
+fn parse_steps(input: &str) -> Vec<Step> {
+    input.trim().split(',').map(Step::parse).collect()
+}
+
+const BOX_COUNT: usize = 256;
+
+/// Lenses in 256 boxes. A box keeps its slots in insertion order and removed lenses leave empty
+/// slots behind, so with the label index every step is O(1) amortized.
+#[derive(Debug)]
+struct Boxes {
+    slots: Vec<Vec<Option<(String, usize)>>>,
+    /// Label to `(box, slot)` of every lens currently in the boxes.
+    index: HashMap<String, (usize, usize)>,
+}
+
+impl Boxes {
+    fn new() -> Self {
+        Boxes {
+            slots: vec![Vec::new(); BOX_COUNT],
+            index: HashMap::new(),
+        }
+    }
+
+    fn apply(&mut self, step: &Step) {
+        match step {
+            Step::Insert { label, focal } => {
+                if let Some((b, slot)) = self.index.get(label) {
+                    self.slots[*b][*slot] = Some((label.clone(), *focal));
+                } else {
+                    let b = hash(label);
+                    self.slots[b].push(Some((label.clone(), *focal)));
+                    self.index
+                        .insert(label.clone(), (b, self.slots[b].len() - 1));
+                }
+            }
+            Step::Remove { label } => {
+                if let Some((b, slot)) = self.index.remove(label) {
+                    self.slots[b][slot] = None;
+                    self.compact(b);
                 }
-            } else {
-                boxes.get_mut(&lens.label_hash).unwrap().push(lens);
             }
         }
     }
 
-    let result = focusing_power(boxes);
+    /// Drops empty slots once they outnumber the lenses in the box.
+    fn compact(&mut self, b: usize) {
+        let used = self.slots[b].iter().flatten().count();
+        if used * 2 >= self.slots[b].len() {
+            return;
+        }
+        self.slots[b].retain(Option::is_some);
+        for (slot, (label, _)) in self.slots[b].iter().flatten().enumerate() {
+            self.index.insert(label.clone(), (b, slot));
+        }
+    }
+
+    fn replay(steps: &[Step]) -> Self {
+        let mut boxes = Boxes::new();
+        for step in steps {
+            boxes.apply(step);
+        }
+        boxes
+    }
+
+    /// `(label, focal length)` of the lenses in box `b`, front to back.
+    fn lenses(&self, b: usize) -> impl Iterator<Item = (&str, usize)> {
+        self.slots[b]
+            .iter()
+            .flatten()
+            .map(|(label, focal)| (label.as_str(), *focal))
+    }
+
+    fn focusing_power(&self) -> usize {
+        (0..BOX_COUNT)
+            .flat_map(|b| {
+                self.lenses(b)
+                    .enumerate()
+                    .map(move |(slot, (_, focal))| (b + 1) * (slot + 1) * focal)
+            })
+            .sum()
+    }
+
+    /// Non-empty boxes, one per line, like `Box 0: [rn 1] [cm 2]`.
+    fn render(&self) -> String {
+        (0..BOX_COUNT)
+            .filter_map(|b| {
+                let lenses: Vec<String> = self
+                    .lenses(b)
+                    .map(|(label, focal)| format!("[{label} {focal}]"))
+                    .collect();
+                (!lenses.is_empty()).then(|| format!("Box {b}: {}", lenses.join(" ")))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// State of the boxes after each step, in the format of the puzzle description.
+fn report(steps: &[Step]) -> String {
+    let mut boxes = Boxes::new();
+    let mut result = Vec::with_capacity(steps.len());
+    for step in steps {
+        boxes.apply(step);
+        let state = boxes.render();
+        if state.is_empty() {
+            result.push(format!("After \"{step}\":"));
+        } else {
+            result.push(format!("After \"{step}\":\n{state}"));
+        }
+    }
+    result.join("\n\n")
+}
+
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
+    let result = Boxes::replay(&parse_steps(input)).focusing_power();
     format!("{result}")
 }
 
@@ -126,23 +187,71 @@ mod tests {
     }
 
     #[test]
-    fn parse_lense_test() {
-        let input1 = "rn=1";
-        let input2 = "cm-";
-        let expected1 = Lens {
-            label: String::from("rn"),
-            label_hash: 0,
-            mode: '=',
-            focal_length: Some(1),
-        };
-        let expected2 = Lens {
-            label: String::from("cm"),
-            label_hash: 0,
-            mode: '-',
-            focal_length: None,
-        };
-        assert_eq!(expected1, Lens::parse(input1));
-        assert_eq!(expected2, Lens::parse(input2));
+    fn parse_step_test() {
+        assert_eq!(
+            Step::Insert {
+                label: String::from("rn"),
+                focal: 1
+            },
+            Step::parse("rn=1")
+        );
+        assert_eq!(
+            Step::Remove {
+                label: String::from("cm")
+            },
+            Step::parse("cm-")
+        );
+        assert_eq!("cm", Step::parse("cm-").label());
+        let steps = parse_steps(TEST_DATA);
+        let text: Vec<String> = steps.iter().map(ToString::to_string).collect();
+        assert_eq!(TEST_DATA, text.join(","));
+    }
+
+    #[test]
+    fn report_test() {
+        let steps = parse_steps(TEST_DATA);
+        let report = report(&steps[..3]);
+        let expected = "After \"rn=1\":
+Box 0: [rn 1]
+
+After \"cm-\":
+Box 0: [rn 1]
+
+After \"qp=3\":
+Box 0: [rn 1]
+Box 1: [qp 3]";
+        assert_eq!(expected, report);
+        assert!(super::report(&steps).ends_with(
+            "After \"ot=7\":
+Box 0: [rn 1] [cm 2]
+Box 3: [ot 7] [ab 5] [pc 6]"
+        ));
+    }
+
+    #[test]
+    fn replay_keeps_order_after_many_removals() {
+        let mut steps = Vec::new();
+        for round in 0..50 {
+            for label in ["rn", "cm", "qp", "pc"] {
+                steps.push(Step::Insert {
+                    label: String::from(label),
+                    focal: round % 9 + 1,
+                });
+            }
+            steps.push(Step::Remove {
+                label: String::from(if round % 2 == 0 { "rn" } else { "cm" }),
+            });
+        }
+        let boxes = Boxes::replay(&steps);
+        assert_eq!(
+            vec![("rn", 5)],
+            boxes.lenses(0).collect::<Vec<(&str, usize)>>()
+        );
+        assert_eq!(
+            vec![("qp", 5)],
+            boxes.lenses(1).collect::<Vec<(&str, usize)>>()
+        );
+        assert_eq!(1, boxes.slots[0].len());
     }
 
     #[test]
@@ -14,6 +14,7 @@ struct Ray {
     position: usize,
 }
 
+#[allow(clippy::large_stack_arrays)]
 fn parse_input(input: &str) -> [char; 110 * 110] {
     let mut result = ['.'; 110 * 110];
     let mut id = 0;
@@ -1,21 +1,16 @@
 #![allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 
 fn parse_input(input: &str) -> Vec<Vec<usize>> {
-    let size = input.lines().count();
-    let mut result = Vec::with_capacity(size);
-    for _i in 0..size {
-        result.push(Vec::with_capacity(size));
-    }
-    for (i, line) in input.lines().enumerate() {
-        for c in line.trim().chars() {
-            result
-                .get_mut(i)
-                .unwrap()
-                .push(format!("{c}").parse::<usize>().unwrap());
-        }
-    }
-    result
+    input
+        .lines()
+        .map(|line| {
+            line.trim()
+                .chars()
+                .map(|c| c.to_digit(10).unwrap() as usize)
+                .collect()
+        })
+        .collect()
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -26,6 +21,7 @@ struct VertexInDijkstra {
     row_delta: isize,
     column_delta: isize,
     steps: isize,
+    previous: Option<VisitedVertex>,
 }
 
 impl VertexInDijkstra {
@@ -44,6 +40,7 @@ impl VertexInDijkstra {
             row_delta,
             column_delta,
             steps,
+            previous: None,
         }
     }
 
@@ -56,9 +53,31 @@ impl VertexInDijkstra {
             steps: self.steps,
         }
     }
+
+    fn next(&self, row_delta: isize, column_delta: isize, board: &[Vec<usize>]) -> Option<Self> {
+        let next_row = self.row + row_delta;
+        let next_column = self.column + column_delta;
+        let cell_heat_loss = board
+            .get(usize::try_from(next_row).ok()?)?
+            .get(usize::try_from(next_column).ok()?)?;
+        let steps = if (row_delta, column_delta) == (self.row_delta, self.column_delta) {
+            self.steps + 1
+        } else {
+            1
+        };
+        Some(VertexInDijkstra {
+            heat_loss: self.heat_loss + cell_heat_loss,
+            row: next_row,
+            column: next_column,
+            row_delta,
+            column_delta,
+            steps,
+            previous: Some(self.to_visited_vertex()),
+        })
+    }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 struct VisitedVertex {
     row: isize,
     column: isize,
@@ -79,154 +98,125 @@ impl Ord for VertexInDijkstra {
     }
 }
 
-/// # Panics
-#[must_use]
-pub fn part1(input: &str) -> String {
-    let board = parse_input(input);
-    let size = board.len() as isize;
-
-    let start = VertexInDijkstra::new(0, 0, 0, 0, 0, 0);
-    let mut q: BinaryHeap<VertexInDijkstra> = BinaryHeap::new();
-    q.push(start);
-
-    let mut visited: HashSet<VisitedVertex> = HashSet::new();
+/// How far the crucible has to move in one direction before it can turn or stop, and how far it
+/// can move before it has to turn.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Crucible {
+    min_straight: isize,
+    max_straight: isize,
+}
 
-    let mut result = 0;
+const CRUCIBLE: Crucible = Crucible {
+    min_straight: 1,
+    max_straight: 3,
+};
 
-    while !&q.is_empty() {
-        let v = q.pop().unwrap();
-        if v.row == size - 1 && v.column == size - 1 {
-            result = v.heat_loss;
-            break;
-        }
+const ULTRA_CRUCIBLE: Crucible = Crucible {
+    min_straight: 4,
+    max_straight: 10,
+};
 
-        if visited.contains(&v.to_visited_vertex()) {
-            continue;
-        }
-
-        visited.insert(v.to_visited_vertex());
-
-        if v.steps < 3 && (v.row_delta, v.column_delta) != (0, 0) {
-            let next_row = v.row + v.row_delta;
-            let next_column = v.column + v.column_delta;
-            if next_row >= 0 && next_column >= 0 && next_row < size && next_column < size {
-                q.push(VertexInDijkstra::new(
-                    v.heat_loss
-                        + board
-                            .get(next_row as usize)
-                            .unwrap()
-                            .get(next_column as usize)
-                            .unwrap(),
-                    next_row,
-                    next_column,
-                    v.row_delta,
-                    v.column_delta,
-                    v.steps + 1,
-                ));
-            }
-        }
+#[derive(Debug, PartialEq)]
+struct CruciblePath {
+    heat_loss: usize,
+    /// Visited `(row, column)` cells, starting with the top-left one.
+    cells: Vec<(usize, usize)>,
+}
 
-        for (row_delta, column_delta) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-            if (row_delta, column_delta) != (v.row_delta, v.column_delta)
-                && (row_delta, column_delta) != (-v.row_delta, -v.column_delta)
-            {
-                let next_row = v.row + row_delta;
-                let next_column = v.column + column_delta;
-                if next_row >= 0 && next_column >= 0 && next_row < size && next_column < size {
-                    q.push(VertexInDijkstra::new(
-                        v.heat_loss
-                            + board
-                                .get(next_row as usize)
-                                .unwrap()
-                                .get(next_column as usize)
-                                .unwrap(),
-                        next_row,
-                        next_column,
-                        row_delta,
-                        column_delta,
-                        1,
-                    ));
-                }
-            }
+impl CruciblePath {
+    /// Draws the path over the board the same way the puzzle description does.
+    fn render(&self, board: &[Vec<usize>]) -> String {
+        let mut canvas: Vec<Vec<char>> = board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|x| char::from_digit(u32::try_from(*x).unwrap(), 10).unwrap())
+                    .collect()
+            })
+            .collect();
+        for window in self.cells.windows(2) {
+            let (from, to) = (window[0], window[1]);
+            canvas[to.0][to.1] = match (to.0.cmp(&from.0), to.1.cmp(&from.1)) {
+                (std::cmp::Ordering::Greater, _) => 'v',
+                (std::cmp::Ordering::Less, _) => '^',
+                (_, std::cmp::Ordering::Greater) => '>',
+                _ => '<',
+            };
         }
+        canvas
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
     }
-
-    format!("{result}")
 }
-/// # Panics
-#[must_use]
-pub fn part2(input: &str) -> String {
-    let board = parse_input(input);
-    let size = board.len() as isize;
 
-    let start = VertexInDijkstra::new(0, 0, 0, 0, 0, 0);
+fn find_path(board: &[Vec<usize>], crucible: Crucible) -> Option<CruciblePath> {
+    let last_row = board.len() as isize - 1;
+    let last_column = board.first()?.len() as isize - 1;
+
     let mut q: BinaryHeap<VertexInDijkstra> = BinaryHeap::new();
-    q.push(start);
+    q.push(VertexInDijkstra::new(0, 0, 0, 0, 0, 0));
 
     let mut visited: HashSet<VisitedVertex> = HashSet::new();
+    let mut previous: HashMap<VisitedVertex, VisitedVertex> = HashMap::new();
 
-    let mut result = 0;
-
-    while !&q.is_empty() {
-        let v = q.pop().unwrap();
-        if v.row == size - 1 && v.column == size - 1 && v.steps >= 4 {
-            result = v.heat_loss;
-            break;
-        }
-
-        if visited.contains(&v.to_visited_vertex()) {
+    while let Some(v) = q.pop() {
+        let vertex = v.to_visited_vertex();
+        if visited.contains(&vertex) {
             continue;
         }
+        visited.insert(vertex);
+        if let Some(p) = v.previous {
+            previous.insert(vertex, p);
+        }
 
-        visited.insert(v.to_visited_vertex());
-
-        if v.steps < 10 && (v.row_delta, v.column_delta) != (0, 0) {
-            let next_row = v.row + v.row_delta;
-            let next_column = v.column + v.column_delta;
-            if next_row >= 0 && next_column >= 0 && next_row < size && next_column < size {
-                q.push(VertexInDijkstra::new(
-                    v.heat_loss
-                        + board
-                            .get(next_row as usize)
-                            .unwrap()
-                            .get(next_column as usize)
-                            .unwrap(),
-                    next_row,
-                    next_column,
-                    v.row_delta,
-                    v.column_delta,
-                    v.steps + 1,
-                ));
+        if v.row == last_row && v.column == last_column && v.steps >= crucible.min_straight {
+            let mut cells = vec![(v.row as usize, v.column as usize)];
+            let mut current = vertex;
+            while let Some(p) = previous.get(&current) {
+                cells.push((p.row as usize, p.column as usize));
+                current = *p;
             }
+            cells.reverse();
+            return Some(CruciblePath {
+                heat_loss: v.heat_loss,
+                cells,
+            });
+        }
+
+        let is_start = (v.row_delta, v.column_delta) == (0, 0);
+        if v.steps < crucible.max_straight && !is_start {
+            q.extend(v.next(v.row_delta, v.column_delta, board));
         }
 
-        if v.steps >= 4 || (v.row_delta, v.column_delta) == (0, 0) {
+        if v.steps >= crucible.min_straight || is_start {
             for (row_delta, column_delta) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
                 if (row_delta, column_delta) != (v.row_delta, v.column_delta)
                     && (row_delta, column_delta) != (-v.row_delta, -v.column_delta)
                 {
-                    let next_row = v.row + row_delta;
-                    let next_column = v.column + column_delta;
-                    if next_row >= 0 && next_column >= 0 && next_row < size && next_column < size {
-                        q.push(VertexInDijkstra::new(
-                            v.heat_loss
-                                + board
-                                    .get(next_row as usize)
-                                    .unwrap()
-                                    .get(next_column as usize)
-                                    .unwrap(),
-                            next_row,
-                            next_column,
-                            row_delta,
-                            column_delta,
-                            1,
-                        ));
-                    }
+                    q.extend(v.next(row_delta, column_delta, board));
                 }
             }
         }
     }
 
+    None
+}
+
+/// # Panics
+#[must_use]
+pub fn part1(input: &str) -> String {
+    let board = parse_input(input);
+    let result = find_path(&board, CRUCIBLE).unwrap().heat_loss;
+    format!("{result}")
+}
+
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
+    let board = parse_input(input);
+    let result = find_path(&board, ULTRA_CRUCIBLE).unwrap().heat_loss;
     format!("{result}")
 }
 
@@ -258,4 +248,66 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("94", part2(TEST_DATA));
     }
+
+    #[test]
+    fn test_case_part2_rectangular() {
+        let input = "111111111111
+        999999999991
+        999999999991
+        999999999991
+        999999999991";
+        assert_eq!("71", part2(input));
+    }
+
+    fn assert_valid_path(board: &[Vec<usize>], crucible: Crucible, path: &CruciblePath) {
+        let heat_loss: usize = path.cells.iter().skip(1).map(|(r, c)| board[*r][*c]).sum();
+        assert_eq!(path.heat_loss, heat_loss);
+        assert_eq!(Some(&(0, 0)), path.cells.first());
+        assert_eq!(
+            Some(&(board.len() - 1, board[0].len() - 1)),
+            path.cells.last()
+        );
+
+        let moves: Vec<(isize, isize)> = path
+            .cells
+            .windows(2)
+            .map(|w| {
+                (
+                    w[1].0 as isize - w[0].0 as isize,
+                    w[1].1 as isize - w[0].1 as isize,
+                )
+            })
+            .collect();
+        let runs: Vec<isize> = moves
+            .chunk_by(|a, b| a == b)
+            .map(|run| run.len() as isize)
+            .collect();
+        assert!(runs
+            .iter()
+            .all(|run| (crucible.min_straight..=crucible.max_straight).contains(run)));
+    }
+
+    #[test]
+    fn find_path_respects_constraints() {
+        let board = parse_input(TEST_DATA);
+        for (crucible, expected) in [(CRUCIBLE, 102), (ULTRA_CRUCIBLE, 94)] {
+            let path = find_path(&board, crucible).unwrap();
+            assert_eq!(expected, path.heat_loss);
+            assert_valid_path(&board, crucible, &path);
+        }
+    }
+
+    #[test]
+    fn find_path_without_route() {
+        let board = parse_input("111\n111");
+        assert_eq!(None, find_path(&board, ULTRA_CRUCIBLE));
+    }
+
+    #[test]
+    fn render_test() {
+        let board = parse_input("1119\n9911");
+        let path = find_path(&board, CRUCIBLE).unwrap();
+        assert_eq!(4, path.heat_loss);
+        assert_eq!("1>>9\n99v>", path.render(&board));
+    }
 }
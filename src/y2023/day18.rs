@@ -1,7 +1,8 @@
-use std::ops::AddAssign;
+use crate::polygon::{Point, Polygon};
+use std::fmt::Write;
 
-#[derive(Debug, PartialEq)]
-enum Direction {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
     Right,
     Left,
     Up,
@@ -15,54 +16,6 @@ struct ColoredSegment {
     color: String,
 }
 
-#[derive(Debug, PartialEq)]
-struct Polygon {
-    vertices: Vec<Vec2>,
-}
-
-impl Polygon {
-    // https://en.m.wikipedia.org/wiki/Shoelace_formula
-    fn shoelace_area(&self) -> usize {
-        let mut sum = 0;
-        let mut counter_clockwise = self.vertices.clone();
-        counter_clockwise.reverse();
-        for window in counter_clockwise.windows(2) {
-            sum += window[0].x * window[1].y;
-            sum -= window[0].y * window[1].x;
-        }
-        (sum / 2).try_into().unwrap()
-    }
-
-    fn boundary_points(segments: &[ColoredSegment]) -> usize {
-        let result: usize = segments.iter().map(|x| x.length as usize).sum();
-        result
-    }
-
-    // https://en.wikipedia.org/wiki/Pick%27s_theorem
-    fn internal_points(boundary: usize, area: usize) -> usize {
-        area - boundary / 2 + 1
-    }
-}
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-struct Vec2 {
-    x: isize,
-    y: isize,
-}
-
-impl Vec2 {
-    fn new(x: isize, y: isize) -> Self {
-        Vec2 { x, y }
-    }
-}
-
-impl AddAssign for Vec2 {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-    }
-}
-
 fn parse_line(line: &str) -> ColoredSegment {
     let mut split = line.trim().split(' ');
     let direction = match split.next().unwrap() {
@@ -85,60 +38,188 @@ fn parse_segments(input: &str) -> Vec<ColoredSegment> {
     input.lines().map(parse_line).collect()
 }
 
-fn parse_data(segments: &Vec<ColoredSegment>) -> Polygon {
-    let mut currect_point = Vec2::new(0, 0);
-    let mut vertices = vec![currect_point];
-    for segment in segments {
-        let change = match segment.direction {
-            Direction::Up => Vec2::new(0, segment.length as isize),
-            Direction::Down => Vec2::new(0, -(segment.length as isize)),
-            Direction::Right => Vec2::new(segment.length as isize, 0),
-            Direction::Left => Vec2::new(-(segment.length as isize), 0),
-        };
-        currect_point += change;
-        vertices.push(currect_point);
+/// One step of a dig plan: dig `length` meters in `direction`.
+pub type Move = (Direction, u64);
+
+impl Direction {
+    fn opposite(self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
     }
-    Polygon { vertices }
 }
 
-// # Panics
-#[must_use]
-pub fn part1(input: &str) -> String {
-    let segments = parse_segments(input);
-    let polygon = parse_data(&segments);
-    let area = polygon.shoelace_area();
-    let boundary = Polygon::boundary_points(&segments);
-    let result = boundary + Polygon::internal_points(boundary, area);
-    format!("{result}")
+/// Reading of the direction letter and length.
+fn letter_move(segment: &ColoredSegment) -> Move {
+    (segment.direction, u64::from(segment.length))
+}
+
+/// Reading of the color: five hex digits of length and one digit of direction.
+fn color_move(segment: &ColoredSegment) -> Move {
+    let length = u64::from_str_radix(&segment.color[0..5], 16).unwrap();
+    let direction = match segment.color.chars().nth(5).unwrap() {
+        '0' => Direction::Right,
+        '1' => Direction::Down,
+        '2' => Direction::Left,
+        '3' => Direction::Up,
+        _ => unreachable!(),
+    };
+    (direction, length)
 }
 
-fn parse_data_part2(segments: &Vec<ColoredSegment>) -> (Polygon, usize) {
-    let mut currect_point = Vec2::new(0, 0);
-    let mut vertices = vec![currect_point];
-    let mut boundary_length = 0;
-    for segment in segments {
-        let length = isize::from_str_radix(&segment.color[0..5], 16).unwrap();
-        boundary_length += length;
-        let change = match segment.color.chars().nth(5).unwrap() {
-            '3' => Vec2::new(0, length),
-            '1' => Vec2::new(0, -length),
-            '0' => Vec2::new(length, 0),
-            '2' => Vec2::new(-length, 0),
-            _ => unreachable!(),
-        };
-        currect_point += change;
-        vertices.push(currect_point);
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlanError {
+    Empty,
+    /// Move with the given index has length zero.
+    ZeroLength(usize),
+    /// The trench ends here instead of at the start.
+    NotClosed(Point),
+    /// Moves with the given indices touch or cross although they aren't consecutive, or are
+    /// consecutive and go back over each other.
+    SelfIntersecting(usize, usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Lagoon {
+    moves: Vec<Move>,
+    trench: Polygon,
+}
+
+impl Lagoon {
+    /// Digs the trench starting at the origin, with up as positive y.
+    ///
+    /// # Errors
+    /// When the plan is empty, has a move of length zero, doesn't end where it started or the
+    /// trench runs into itself.
+    ///
+    /// # Panics
+    /// When a length doesn't fit in an `i64`.
+    pub fn dig(moves: impl IntoIterator<Item = Move>) -> Result<Lagoon, PlanError> {
+        let moves: Vec<Move> = moves.into_iter().collect();
+        if moves.is_empty() {
+            return Err(PlanError::Empty);
+        }
+        if let Some(index) = moves.iter().position(|(_, length)| *length == 0) {
+            return Err(PlanError::ZeroLength(index));
+        }
+
+        let mut current = Point::new(0, 0);
+        let mut vertices = vec![current];
+        for (direction, length) in &moves {
+            let length = i64::try_from(*length).unwrap();
+            match direction {
+                Direction::Up => current.y += length,
+                Direction::Down => current.y -= length,
+                Direction::Right => current.x += length,
+                Direction::Left => current.x -= length,
+            }
+            vertices.push(current);
+        }
+        if current != vertices[0] {
+            return Err(PlanError::NotClosed(current));
+        }
+        if let Some((first, second)) = find_intersection(&moves, &vertices) {
+            return Err(PlanError::SelfIntersecting(first, second));
+        }
+        Ok(Lagoon {
+            moves,
+            trench: Polygon::new(vertices),
+        })
+    }
+
+    #[must_use]
+    pub fn trench(&self) -> &Polygon {
+        &self.trench
+    }
+
+    /// Cubic meters dug out, the trench included.
+    #[must_use]
+    pub fn volume(&self) -> u64 {
+        // Validated trenches are simple and enclose some area, so the count always exists
+        self.trench.covered_points().unwrap_or_default()
+    }
+
+    /// Path data for the `d` attribute of an SVG `<path>`. SVG has y pointing down, so up moves
+    /// become negative vertical steps.
+    #[must_use]
+    pub fn svg_path(&self) -> String {
+        let mut result = String::from("M 0 0");
+        for (direction, length) in &self.moves {
+            let _ = match direction {
+                Direction::Right => write!(result, " h {length}"),
+                Direction::Left => write!(result, " h -{length}"),
+                Direction::Up => write!(result, " v -{length}"),
+                Direction::Down => write!(result, " v {length}"),
+            };
+        }
+        result.push_str(" Z");
+        result
+    }
+
+    /// Standalone SVG document showing the trench.
+    #[must_use]
+    pub fn svg(&self) -> String {
+        let vertices = self.trench.vertices();
+        let min_x = vertices.iter().map(|p| p.x).min().unwrap_or_default();
+        let max_x = vertices.iter().map(|p| p.x).max().unwrap_or_default();
+        let min_y = vertices.iter().map(|p| -p.y).min().unwrap_or_default();
+        let max_y = vertices.iter().map(|p| -p.y).max().unwrap_or_default();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n  \
+             <path d=\"{}\" fill=\"none\" stroke=\"black\" vector-effect=\"non-scaling-stroke\"/>\n\
+             </svg>",
+            min_x - 1,
+            min_y - 1,
+            max_x - min_x + 2,
+            max_y - min_y + 2,
+            self.svg_path()
+        )
+    }
+}
+
+/// First pair of moves whose segments share a point they shouldn't. All segments are axis
+/// parallel, so they meet exactly when their bounding boxes do.
+fn find_intersection(moves: &[Move], vertices: &[Point]) -> Option<(usize, usize)> {
+    let count = moves.len();
+    for i in 0..count {
+        let next = (i + 1) % count;
+        if moves[next].0 == moves[i].0.opposite() {
+            return Some((i.min(next), i.max(next)));
+        }
     }
-    (Polygon { vertices }, boundary_length.try_into().unwrap())
+    let overlap = |a: i64, b: i64, c: i64, d: i64| a.min(b) <= c.max(d) && c.min(d) <= a.max(b);
+    for i in 0..count {
+        // The last segment follows the first one, so skip it together with the next one
+        let end = if i == 0 { count - 1 } else { count };
+        for j in i + 2..end {
+            let (a, b) = (vertices[i], vertices[i + 1]);
+            let (c, d) = (vertices[j], vertices[j + 1]);
+            if overlap(a.x, b.x, c.x, d.x) && overlap(a.y, b.y, c.y, d.y) {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+fn lagoon(input: &str, decode: fn(&ColoredSegment) -> Move) -> Lagoon {
+    Lagoon::dig(parse_segments(input).iter().map(decode)).unwrap()
+}
+
+/// # Panics
+#[must_use]
+pub fn part1(input: &str) -> String {
+    let result = lagoon(input, letter_move).volume();
+    format!("{result}")
 }
 
-// # Panics
+/// # Panics
 #[must_use]
 pub fn part2(input: &str) -> String {
-    let segments = parse_segments(input);
-    let (polygon, boundary) = parse_data_part2(&segments);
-    let area = polygon.shoelace_area();
-    let result = boundary + Polygon::internal_points(boundary, area);
+    let result = lagoon(input, color_move).volume();
     format!("{result}")
 }
 
@@ -171,4 +252,74 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("952408144115", part2(TEST_DATA));
     }
+
+    #[test]
+    fn both_encodings() {
+        let segments = parse_segments(TEST_DATA);
+        assert_eq!((Direction::Right, 6), letter_move(&segments[0]));
+        assert_eq!((Direction::Right, 461_937), color_move(&segments[0]));
+        assert_eq!((Direction::Up, 500_254), color_move(&segments[13]));
+    }
+
+    #[test]
+    fn custom_plan() {
+        let moves = [
+            (Direction::Right, 2),
+            (Direction::Down, 2),
+            (Direction::Left, 2),
+            (Direction::Up, 2),
+        ];
+        let lagoon = Lagoon::dig(moves).unwrap();
+        assert_eq!(9, lagoon.volume());
+        assert_eq!(4, lagoon.trench().vertices().len());
+        assert_eq!("M 0 0 h 2 v 2 h -2 v -2 Z", lagoon.svg_path());
+        assert_eq!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -1 4 4\">
+  <path d=\"M 0 0 h 2 v 2 h -2 v -2 Z\" fill=\"none\" stroke=\"black\" vector-effect=\"non-scaling-stroke\"/>
+</svg>",
+            lagoon.svg()
+        );
+    }
+
+    #[test]
+    fn invalid_plans() {
+        assert_eq!(Err(PlanError::Empty), Lagoon::dig([]));
+        assert_eq!(
+            Err(PlanError::ZeroLength(1)),
+            Lagoon::dig([(Direction::Right, 2), (Direction::Down, 0)])
+        );
+        assert_eq!(
+            Err(PlanError::NotClosed(Point::new(2, -3))),
+            Lagoon::dig([(Direction::Right, 2), (Direction::Down, 3)])
+        );
+        assert_eq!(
+            Err(PlanError::SelfIntersecting(0, 1)),
+            Lagoon::dig([(Direction::Right, 2), (Direction::Left, 2)])
+        );
+        // Figure eight crossing itself at (1, -1)
+        let eight = [
+            (Direction::Right, 2),
+            (Direction::Down, 2),
+            (Direction::Left, 1),
+            (Direction::Up, 3),
+            (Direction::Left, 1),
+            (Direction::Down, 1),
+        ];
+        assert_eq!(Err(PlanError::SelfIntersecting(0, 3)), Lagoon::dig(eight));
+        // Two loops touching at a corner
+        let touching = [
+            (Direction::Right, 1),
+            (Direction::Down, 1),
+            (Direction::Right, 1),
+            (Direction::Down, 1),
+            (Direction::Left, 1),
+            (Direction::Up, 1),
+            (Direction::Left, 1),
+            (Direction::Up, 1),
+        ];
+        assert_eq!(
+            Err(PlanError::SelfIntersecting(1, 5)),
+            Lagoon::dig(touching)
+        );
+    }
 }
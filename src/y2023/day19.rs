@@ -1,14 +1,52 @@
+use crate::interval::{Interval, IntervalBox};
 use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum Relation {
     Greater,
+    GreaterOrEqual,
     Less,
+    LessOrEqual,
+    Equal,
+}
+
+impl Relation {
+    /// Two character operators go first, so that `<=` isn't taken for `<`.
+    const SYMBOLS: [(&'static str, Relation); 5] = [
+        ("<=", Relation::LessOrEqual),
+        (">=", Relation::GreaterOrEqual),
+        ("==", Relation::Equal),
+        ("<", Relation::Less),
+        (">", Relation::Greater),
+    ];
+
+    fn holds(self, rating: usize, value: usize) -> bool {
+        match self {
+            Relation::Greater => rating > value,
+            Relation::GreaterOrEqual => rating >= value,
+            Relation::Less => rating < value,
+            Relation::LessOrEqual => rating <= value,
+            Relation::Equal => rating == value,
+        }
+    }
+
+    /// All ratings for which the relation holds.
+    fn interval(self, value: usize) -> Option<Interval<usize>> {
+        match self {
+            Relation::Greater => value
+                .checked_add(1)
+                .and_then(|from| Interval::new(from, usize::MAX)),
+            Relation::GreaterOrEqual => Interval::new(value, usize::MAX),
+            Relation::Less => value.checked_sub(1).and_then(|to| Interval::new(0, to)),
+            Relation::LessOrEqual => Interval::new(0, value),
+            Relation::Equal => Interval::new(value, value),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 struct Rule {
-    dimension: Option<char>,
+    dimension: Option<String>,
     relation: Option<Relation>,
     value: Option<usize>,
     goto: String,
@@ -16,186 +54,24 @@ struct Rule {
 
 impl Rule {
     fn parse(input: &str) -> Self {
-        if input.contains(':') {
-            let mut split = input.split(':');
-            let first_part = split.next().unwrap();
-            let dimension;
-            let relation;
-            let value;
-            if first_part.contains('<') {
-                let mut inner_split = first_part.split('<');
-                dimension = inner_split.next().unwrap().chars().next().unwrap();
-                relation = Relation::Less;
-                value = inner_split.next().unwrap().parse().unwrap();
-            } else {
-                let mut inner_split = first_part.split('>');
-                relation = Relation::Greater;
-                dimension = inner_split.next().unwrap().chars().next().unwrap();
-                value = inner_split.next().unwrap().parse().unwrap();
-            }
-
-            let goto = String::from(split.next().unwrap());
-            Rule {
-                dimension: Some(dimension),
-                relation: Some(relation),
-                value: Some(value),
-                goto,
-            }
-        } else {
-            Rule {
+        let Some((condition, goto)) = input.split_once(':') else {
+            return Rule {
                 dimension: None,
                 relation: None,
                 value: None,
                 goto: String::from(input),
-            }
-        }
-    }
-
-    fn applies(&self, part: &Part) -> Option<String> {
-        if self.dimension.is_none() {
-            return Some(self.goto.clone());
-        }
-        match (&self.dimension.unwrap(), &self.relation.unwrap()) {
-            ('x', Relation::Greater) => {
-                if part.x > self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('x', Relation::Less) => {
-                if part.x < self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('m', Relation::Greater) => {
-                if part.m > self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('m', Relation::Less) => {
-                if part.m < self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('a', Relation::Greater) => {
-                if part.a > self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('a', Relation::Less) => {
-                if part.a < self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('s', Relation::Greater) => {
-                if part.s > self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            ('s', Relation::Less) => {
-                if part.s < self.value.unwrap() {
-                    return Some(self.goto.clone());
-                }
-                None
-            }
-            _ => unreachable!(),
-        }
-    }
-
-    fn split_range(&self, ranges: &Ranges) -> (String, Option<Ranges>, Option<Ranges>) {
-        let goto = self.goto.clone();
-        if self.dimension.is_none() {
-            return (goto, Some(*ranges), None);
-        }
-        let value = self.value.unwrap();
-        match (&self.dimension.unwrap(), &self.relation.unwrap()) {
-            ('x', Relation::Greater) => {
-                if value > ranges.x_max {
-                    return (goto, None, Some(*ranges));
-                }
-                if value < ranges.x_min {
-                    return (goto, Some(*ranges), None);
-                }
-                let (r1, r2) = ranges.split('x', value);
-                (goto, r2, Some(r1))
-            }
-            ('x', Relation::Less) => {
-                if value > ranges.x_max {
-                    return (goto, Some(*ranges), None);
-                }
-                if value < ranges.x_min {
-                    return (goto, None, Some(*ranges));
-                }
-                let (r1, r2) = ranges.split('x', value - 1);
-                (goto, Some(r1), r2)
-            }
-            ('m', Relation::Greater) => {
-                if value > ranges.m_max {
-                    return (goto, None, Some(*ranges));
-                }
-                if value < ranges.m_min {
-                    return (goto, Some(*ranges), None);
-                }
-                let (r1, r2) = ranges.split('m', value);
-                (goto, r2, Some(r1))
-            }
-            ('m', Relation::Less) => {
-                if value > ranges.m_max {
-                    return (goto, Some(*ranges), None);
-                }
-                if value < ranges.m_min {
-                    return (goto, None, Some(*ranges));
-                }
-                let (r1, r2) = ranges.split('m', value - 1);
-                (goto, Some(r1), r2)
-            }
-            ('a', Relation::Greater) => {
-                if value > ranges.a_max {
-                    return (goto, None, Some(*ranges));
-                }
-                if value < ranges.a_min {
-                    return (goto, Some(*ranges), None);
-                }
-                let (r1, r2) = ranges.split('a', value);
-                (goto, r2, Some(r1))
-            }
-            ('a', Relation::Less) => {
-                if value > ranges.a_max {
-                    return (goto, Some(*ranges), None);
-                }
-                if value < ranges.a_min {
-                    return (goto, None, Some(*ranges));
-                }
-                let (r1, r2) = ranges.split('a', value - 1);
-                (goto, Some(r1), r2)
-            }
-            ('s', Relation::Greater) => {
-                if value > ranges.s_max {
-                    return (goto, None, Some(*ranges));
-                }
-                if value < ranges.s_min {
-                    return (goto, Some(*ranges), None);
-                }
-                let (r1, r2) = ranges.split('s', value);
-                (goto, r2, Some(r1))
-            }
-            ('s', Relation::Less) => {
-                if value > ranges.s_max {
-                    return (goto, Some(*ranges), None);
-                }
-                if value < ranges.s_min {
-                    return (goto, None, Some(*ranges));
-                }
-                let (r1, r2) = ranges.split('s', value - 1);
-                (goto, Some(r1), r2)
-            }
-            _ => unreachable!(),
+            };
+        };
+        let (symbol, relation) = Relation::SYMBOLS
+            .iter()
+            .find(|(symbol, _)| condition.contains(symbol))
+            .unwrap();
+        let (dimension, value) = condition.split_once(symbol).unwrap();
+        Rule {
+            dimension: Some(dimension.trim().to_owned()),
+            relation: Some(*relation),
+            value: Some(value.trim().parse().unwrap()),
+            goto: String::from(goto),
         }
     }
 }
@@ -223,288 +99,421 @@ impl Workflow {
 
 #[derive(Debug, PartialEq)]
 struct Part {
-    x: usize,
-    m: usize,
-    a: usize,
-    s: usize,
+    ratings: Vec<(String, usize)>,
 }
 
 impl Part {
-    fn new(x: usize, m: usize, a: usize, s: usize) -> Self {
-        Part { x, m, a, s }
-    }
-
     fn parse(input: &str) -> Self {
-        let mut split = input.trim().split('=');
-        let _first = split.next().unwrap();
-        let x = split
-            .next()
-            .unwrap()
-            .split(',')
-            .next()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let m = split
-            .next()
-            .unwrap()
-            .split(',')
-            .next()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let a = split
-            .next()
-            .unwrap()
+        let ratings = input
+            .trim()
+            .trim_start_matches('{')
+            .trim_end_matches('}')
             .split(',')
-            .next()
-            .unwrap()
-            .parse()
-            .unwrap();
-        let s = split
-            .next()
-            .unwrap()
-            .split('}')
-            .next()
-            .unwrap()
-            .parse()
-            .unwrap();
-        Part { x, m, a, s }
+            .map(|rating| {
+                let (name, value) = rating.split_once('=').unwrap();
+                (name.trim().to_owned(), value.trim().parse().unwrap())
+            })
+            .collect();
+        Part { ratings }
     }
 
-    fn xmas_rating(&self) -> usize {
-        self.x + self.m + self.a + self.s
+    fn total_rating(&self) -> usize {
+        self.ratings.iter().map(|(_, value)| value).sum()
+    }
+
+    fn rating(&self, attribute: &str) -> Option<usize> {
+        self.ratings
+            .iter()
+            .find(|(name, _)| name == attribute)
+            .map(|(_, value)| *value)
     }
 }
 
 fn parse_data(input: &str) -> (Vec<Workflow>, Vec<Part>) {
     let mut split = input.split("\n\n");
     let workflows = split.next().unwrap().lines().map(Workflow::parse).collect();
-    let parts = split.next().unwrap().lines().map(Part::parse).collect();
+    let parts = split
+        .next()
+        .unwrap_or_default()
+        .lines()
+        .map(Part::parse)
+        .collect();
     (workflows, parts)
 }
 
-fn workflows_to_map(workflows: Vec<Workflow>) -> HashMap<String, Vec<Rule>> {
-    let mut result = HashMap::new();
-
-    for workflow in workflows {
-        result.insert(workflow.name, workflow.rules);
+/// Attribute names in order of their first appearance in the parts.
+fn part_attributes(parts: &[Part]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for (name, _) in parts.iter().flat_map(|part| &part.ratings) {
+        if !result.contains(name) {
+            result.push(name.clone());
+        }
     }
-
     result
 }
 
-fn eval(part: &Part, workflow_map: &HashMap<String, Vec<Rule>>) -> usize {
-    let mut current_state = String::from("in");
+type Ranges = IntervalBox<usize>;
 
-    'outer: while &current_state != "A" && &current_state != "R" {
-        for rule in workflow_map.get(&current_state).unwrap() {
-            if let Some(goto) = rule.applies(part) {
-                current_state = goto;
-                continue 'outer;
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Condition {
+    attribute: usize,
+    relation: Relation,
+    value: usize,
+}
+
+impl Condition {
+    /// Splits `ranges` into the part matching the condition and disjoint boxes covering the rest.
+    fn split(&self, ranges: &Ranges) -> (Option<Ranges>, Vec<Ranges>) {
+        let matching = self
+            .relation
+            .interval(self.value)
+            .and_then(|interval| ranges.intersect(&ranges.with_axis(self.attribute, interval)));
+        match matching {
+            Some(matching) => {
+                let rest = ranges.subtract(&matching);
+                (Some(matching), rest)
             }
+            None => (None, vec![ranges.clone()]),
         }
     }
+}
 
-    if current_state == "R" {
-        0
-    } else {
-        part.xmas_rating()
-    }
+#[derive(Debug, PartialEq)]
+struct CompiledRule {
+    condition: Option<Condition>,
+    target: Target,
 }
 
-/// # Panics
-#[must_use]
-pub fn part1(input: &str) -> String {
-    let (workflows, parts) = parse_data(input);
-    let workflow_map = workflows_to_map(workflows);
-    let mut result = 0;
+#[derive(Debug, PartialEq)]
+enum WorkflowError {
+    MissingStart,
+    DanglingTarget {
+        workflow: String,
+        target: String,
+    },
+    /// Workflow names along the cycle, starting and ending with the same one.
+    Cycle(Vec<String>),
+    /// Workflow which no part starting at `in` gets to.
+    UnreachableWorkflow(String),
+    /// Rule (counted from 0) which no part can ever take.
+    UnreachableRule {
+        workflow: String,
+        rule: usize,
+    },
+}
+
+/// Workflows with names and attributes replaced by indices.
+#[derive(Debug)]
+struct DecisionTree {
+    workflow_names: Vec<String>,
+    attributes: Vec<String>,
+    workflows: Vec<Vec<CompiledRule>>,
+    start: usize,
+}
 
-    for part in parts {
-        result += eval(&part, &workflow_map);
+impl DecisionTree {
+    /// Fails on problems that make evaluation impossible: a missing `in` workflow, targets
+    /// pointing to unknown workflows and cycles.
+    fn compile(workflows: &[Workflow], attributes: &[String]) -> Result<Self, Vec<WorkflowError>> {
+        let (tree, errors) = Self::build(workflows, attributes);
+        if errors.is_empty() {
+            Ok(tree)
+        } else {
+            Err(errors)
+        }
     }
 
-    format!("{result}")
-}
+    /// Every problem found in the workflows, including workflows and rules unreachable for
+    /// ratings in `domain`.
+    fn validate(
+        workflows: &[Workflow],
+        attributes: &[String],
+        domain: Interval<usize>,
+    ) -> Vec<WorkflowError> {
+        let (tree, mut errors) = Self::build(workflows, attributes);
+        if !errors.contains(&WorkflowError::MissingStart) {
+            errors.extend(tree.unreachable_rules(domain));
+        }
+        errors
+    }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-struct Ranges {
-    x_min: usize,
-    x_max: usize,
-    m_min: usize,
-    m_max: usize,
-    a_min: usize,
-    a_max: usize,
-    s_min: usize,
-    s_max: usize,
-}
+    fn build(workflows: &[Workflow], attributes: &[String]) -> (Self, Vec<WorkflowError>) {
+        let mut errors = Vec::new();
+        let ids: HashMap<&str, usize> = workflows
+            .iter()
+            .enumerate()
+            .map(|(id, workflow)| (workflow.name.as_str(), id))
+            .collect();
+        let mut attributes = attributes.to_vec();
+
+        let mut compiled = Vec::with_capacity(workflows.len());
+        for workflow in workflows {
+            let mut rules = Vec::with_capacity(workflow.rules.len());
+            for rule in &workflow.rules {
+                let target = match rule.goto.as_str() {
+                    "A" => Target::Accept,
+                    "R" => Target::Reject,
+                    name => {
+                        if let Some(id) = ids.get(name) {
+                            Target::Workflow(*id)
+                        } else {
+                            errors.push(WorkflowError::DanglingTarget {
+                                workflow: workflow.name.clone(),
+                                target: rule.goto.clone(),
+                            });
+                            Target::Reject
+                        }
+                    }
+                };
+                let condition = rule.dimension.as_ref().map(|dimension| {
+                    let attribute = attributes
+                        .iter()
+                        .position(|a| a == dimension)
+                        .unwrap_or_else(|| {
+                            attributes.push(dimension.clone());
+                            attributes.len() - 1
+                        });
+                    Condition {
+                        attribute,
+                        relation: rule.relation.unwrap(),
+                        value: rule.value.unwrap(),
+                    }
+                });
+                rules.push(CompiledRule { condition, target });
+            }
+            compiled.push(rules);
+        }
 
-impl Ranges {
-    #[allow(clippy::too_many_arguments)]
-    fn new(
-        x_min: usize,
-        x_max: usize,
-        m_min: usize,
-        m_max: usize,
-        a_min: usize,
-        a_max: usize,
-        s_min: usize,
-        s_max: usize,
-    ) -> Self {
-        Ranges {
-            x_min,
-            x_max,
-            m_min,
-            m_max,
-            a_min,
-            a_max,
-            s_min,
-            s_max,
+        let start = ids.get("in").copied();
+        if start.is_none() {
+            errors.push(WorkflowError::MissingStart);
         }
+
+        let tree = DecisionTree {
+            workflow_names: workflows.iter().map(|w| w.name.clone()).collect(),
+            attributes,
+            workflows: compiled,
+            start: start.unwrap_or_default(),
+        };
+        errors.extend(tree.cycles());
+        (tree, errors)
     }
 
-    fn number_of_elements(&self) -> usize {
-        (self.x_max - self.x_min + 1)
-            * (self.m_max - self.m_min + 1)
-            * (self.a_max - self.a_min + 1)
-            * (self.s_max - self.s_min + 1)
+    fn successors(&self, workflow: usize) -> impl Iterator<Item = usize> + '_ {
+        self.workflows[workflow]
+            .iter()
+            .filter_map(|rule| match rule.target {
+                Target::Workflow(id) => Some(id),
+                _ => None,
+            })
     }
 
-    fn split(&self, dimension: char, value: usize) -> (Ranges, Option<Ranges>) {
-        match dimension {
-            'x' => {
-                if value < self.x_min || value > self.x_max {
-                    return (*self, None);
-                }
-                (
-                    Ranges::new(
-                        self.x_min, value, self.m_min, self.m_max, self.a_min, self.a_max,
-                        self.s_min, self.s_max,
-                    ),
-                    Some(Ranges::new(
-                        value + 1,
-                        self.x_max,
-                        self.m_min,
-                        self.m_max,
-                        self.a_min,
-                        self.a_max,
-                        self.s_min,
-                        self.s_max,
-                    )),
-                )
+    /// One cycle for every back edge found by depth-first search.
+    fn cycles(&self) -> Vec<WorkflowError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            OnStack,
+            Done,
+        }
+
+        let mut result = Vec::new();
+        let mut state = vec![State::New; self.workflows.len()];
+        for root in 0..self.workflows.len() {
+            if state[root] != State::New {
+                continue;
             }
-            'm' => {
-                if value < self.m_min || value > self.m_max {
-                    return (*self, None);
+            let mut path = vec![root];
+            let mut stack = vec![self.successors(root).collect::<Vec<usize>>()];
+            state[root] = State::OnStack;
+            while let Some(successors) = stack.last_mut() {
+                let Some(next) = successors.pop() else {
+                    state[path.pop().unwrap()] = State::Done;
+                    stack.pop();
+                    continue;
+                };
+                match state[next] {
+                    State::New => {
+                        state[next] = State::OnStack;
+                        path.push(next);
+                        stack.push(self.successors(next).collect());
+                    }
+                    State::OnStack => {
+                        let begin = path.iter().position(|id| *id == next).unwrap();
+                        let cycle = path[begin..]
+                            .iter()
+                            .chain([&next])
+                            .map(|id| self.workflow_names[*id].clone())
+                            .collect();
+                        result.push(WorkflowError::Cycle(cycle));
+                    }
+                    State::Done => {}
                 }
-                (
-                    Ranges::new(
-                        self.x_min, self.x_max, self.m_min, value, self.a_min, self.a_max,
-                        self.s_min, self.s_max,
-                    ),
-                    Some(Ranges::new(
-                        self.x_min,
-                        self.x_max,
-                        value + 1,
-                        self.m_max,
-                        self.a_min,
-                        self.a_max,
-                        self.s_min,
-                        self.s_max,
-                    )),
-                )
             }
-            'a' => {
-                if value < self.a_min || value > self.a_max {
-                    return (*self, None);
-                }
-                (
-                    Ranges::new(
-                        self.x_min, self.x_max, self.m_min, self.m_max, self.a_min, value,
-                        self.s_min, self.s_max,
-                    ),
-                    Some(Ranges::new(
-                        self.x_min,
-                        self.x_max,
-                        self.m_min,
-                        self.m_max,
-                        value + 1,
-                        self.a_max,
-                        self.s_min,
-                        self.s_max,
-                    )),
-                )
+        }
+        result
+    }
+
+    /// Workflows no part starting at `in` ever gets to and rules no part gets to take, for
+    /// ratings in `domain`. Parts are followed from `in` like in [`DecisionTree::accepted_boxes`];
+    /// a workflow only passes on the parts that haven't reached it before, which ends the search
+    /// even in cycles.
+    fn unreachable_rules(&self, domain: Interval<usize>) -> Vec<WorkflowError> {
+        let mut reached: Vec<Vec<Ranges>> = vec![Vec::new(); self.workflows.len()];
+        let mut taken: Vec<Vec<bool>> = self
+            .workflows
+            .iter()
+            .map(|rules| vec![false; rules.len()])
+            .collect();
+        let mut queue = vec![(self.start, Ranges::cube(domain, self.attributes.len()))];
+        while let Some((workflow, ranges)) = queue.pop() {
+            let mut reaching = vec![ranges];
+            for seen in &reached[workflow] {
+                reaching = reaching.iter().flat_map(|r| r.subtract(seen)).collect();
             }
-            's' => {
-                if value < self.s_min || value > self.s_max {
-                    return (*self, None);
+            reached[workflow].extend(reaching.iter().cloned());
+
+            for (index, rule) in self.workflows[workflow].iter().enumerate() {
+                let matching = match &rule.condition {
+                    None => std::mem::take(&mut reaching),
+                    Some(condition) => {
+                        let mut matching = Vec::new();
+                        let mut rest = Vec::new();
+                        for ranges in &reaching {
+                            let (matched, left) = condition.split(ranges);
+                            matching.extend(matched);
+                            rest.extend(left);
+                        }
+                        reaching = rest;
+                        matching
+                    }
+                };
+                taken[workflow][index] |= !matching.is_empty();
+                if let Target::Workflow(id) = rule.target {
+                    queue.extend(matching.into_iter().map(|m| (id, m)));
                 }
-                (
-                    Ranges::new(
-                        self.x_min, self.x_max, self.m_min, self.m_max, self.a_min, self.a_max,
-                        self.s_min, value,
-                    ),
-                    Some(Ranges::new(
-                        self.x_min,
-                        self.x_max,
-                        self.m_min,
-                        self.m_max,
-                        self.a_min,
-                        self.a_max,
-                        value + 1,
-                        self.s_max,
-                    )),
-                )
             }
-            _ => unreachable!(),
         }
+
+        let mut result = Vec::new();
+        for (id, rules_taken) in taken.iter().enumerate() {
+            let workflow = &self.workflow_names[id];
+            if reached[id].is_empty() {
+                result.push(WorkflowError::UnreachableWorkflow(workflow.clone()));
+                continue;
+            }
+            for (rule, _) in rules_taken.iter().enumerate().filter(|(_, taken)| !**taken) {
+                result.push(WorkflowError::UnreachableRule {
+                    workflow: workflow.clone(),
+                    rule,
+                });
+            }
+        }
+        result
     }
-}
 
-fn eval_part2(
-    current_state: &str,
-    workflow_map: &HashMap<String, Vec<Rule>>,
-    ranges: Ranges,
-) -> usize {
-    if current_state == "A" {
-        return ranges.number_of_elements();
-    } else if current_state == "R" {
-        return 0;
+    /// Ratings ordered like `self.attributes`, missing ones are 0.
+    fn ratings(&self, part: &Part) -> Vec<usize> {
+        self.attributes
+            .iter()
+            .map(|attribute| part.rating(attribute).unwrap_or(0))
+            .collect()
     }
 
-    let mut result = 0;
-    let rules = workflow_map.get(current_state).unwrap();
-    let mut current_range = Some(ranges);
-    for rule in rules {
-        let (goto, r1, r2) = rule.split_range(&current_range.unwrap());
-        if let Some(r) = r1 {
-            result += eval_part2(&goto, workflow_map, r);
+    fn accepts(&self, part: &Part) -> bool {
+        let ratings = self.ratings(part);
+        let mut current = self.start;
+        loop {
+            let rule = self.workflows[current]
+                .iter()
+                .find(|rule| {
+                    rule.condition.as_ref().is_none_or(|condition| {
+                        condition
+                            .relation
+                            .holds(ratings[condition.attribute], condition.value)
+                    })
+                })
+                .unwrap();
+            match rule.target {
+                Target::Accept => return true,
+                Target::Reject => return false,
+                Target::Workflow(id) => current = id,
+            }
         }
-        current_range = r2;
     }
 
-    result
+    /// Disjoint boxes, with axes ordered like `self.attributes`, containing exactly the accepted
+    /// parts with all ratings in `domain`.
+    fn accepted_boxes(&self, domain: Interval<usize>) -> Vec<Ranges> {
+        let mut result = Vec::new();
+        let mut queue = vec![(
+            Target::Workflow(self.start),
+            Ranges::cube(domain, self.attributes.len()),
+        )];
+        while let Some((target, ranges)) = queue.pop() {
+            let workflow = match target {
+                Target::Accept => {
+                    result.push(ranges);
+                    continue;
+                }
+                Target::Reject => continue,
+                Target::Workflow(id) => id,
+            };
+            let mut reaching = vec![ranges];
+            for rule in &self.workflows[workflow] {
+                match &rule.condition {
+                    None => {
+                        queue.extend(reaching.drain(..).map(|r| (rule.target, r)));
+                    }
+                    Some(condition) => {
+                        let mut rest = Vec::new();
+                        for ranges in &reaching {
+                            let (matching, left) = condition.split(ranges);
+                            queue.extend(matching.map(|m| (rule.target, m)));
+                            rest.extend(left);
+                        }
+                        reaching = rest;
+                    }
+                }
+            }
+        }
+        result
+    }
 }
+
+/// # Panics
+#[must_use]
+pub fn part1(input: &str) -> String {
+    let (workflows, parts) = parse_data(input);
+    let tree = DecisionTree::compile(&workflows, &part_attributes(&parts)).unwrap();
+    let result: usize = parts
+        .iter()
+        .filter(|part| tree.accepts(part))
+        .map(Part::total_rating)
+        .sum();
+
+    format!("{result}")
+}
+
+const XMAS: [&str; 4] = ["x", "m", "a", "s"];
+
 /// # Panics
 #[must_use]
 pub fn part2(input: &str) -> String {
     let (workflows, _) = parse_data(input);
-    let workflow_map = workflows_to_map(workflows);
-
-    let result = eval_part2(
-        "in",
-        &workflow_map,
-        Ranges {
-            x_min: 1,
-            x_max: 4000,
-            m_min: 1,
-            m_max: 4000,
-            a_min: 1,
-            a_max: 4000,
-            s_min: 1,
-            s_max: 4000,
-        },
-    );
+    let tree = DecisionTree::compile(&workflows, &XMAS.map(String::from)).unwrap();
+    let result: usize = tree
+        .accepted_boxes(Interval::new(1, 4000).unwrap())
+        .iter()
+        .map(|ranges| ranges.volume().unwrap())
+        .sum();
 
     format!("{result}")
 }
@@ -536,10 +545,12 @@ hdj{m>838:A,pv}
     fn parse_part() {
         let input = "{x=2036,m=264,a=79,s=2244}";
         let expected = Part {
-            x: 2036,
-            m: 264,
-            a: 79,
-            s: 2244,
+            ratings: vec![
+                (String::from("x"), 2036),
+                (String::from("m"), 264),
+                (String::from("a"), 79),
+                (String::from("s"), 2244),
+            ],
         };
         assert_eq!(expected, Part::parse(input));
     }
@@ -548,7 +559,7 @@ hdj{m>838:A,pv}
     fn parse_rule_with_optional_values() {
         let input = "a<2006:qkq";
         let expected = Rule {
-            dimension: Some('a'),
+            dimension: Some(String::from("a")),
             relation: Some(Relation::Less),
             value: Some(2006),
             goto: String::from("qkq"),
@@ -575,13 +586,13 @@ hdj{m>838:A,pv}
             name: String::from("px"),
             rules: vec![
                 Rule {
-                    dimension: Some('a'),
+                    dimension: Some(String::from("a")),
                     relation: Some(Relation::Less),
                     value: Some(2006),
                     goto: String::from("qkq"),
                 },
                 Rule {
-                    dimension: Some('m'),
+                    dimension: Some(String::from("m")),
                     relation: Some(Relation::Greater),
                     value: Some(2090),
                     goto: String::from("A"),
@@ -603,11 +614,178 @@ hdj{m>838:A,pv}
     }
 
     #[test]
-    fn split_ranges() {
-        let range = Ranges::new(0, 1000, 0, 1000, 0, 1000, 0, 1000);
-        let expected1 = Ranges::new(0, 1000, 0, 500, 0, 1000, 0, 1000);
-        let expected2 = Ranges::new(0, 1000, 501, 1000, 0, 1000, 0, 1000);
-        assert_eq!((expected1, Some(expected2)), range.split('m', 500));
+    fn parse_rule_with_other_relations() {
+        let input = "weight>=15:heavy";
+        let expected = Rule {
+            dimension: Some(String::from("weight")),
+            relation: Some(Relation::GreaterOrEqual),
+            value: Some(15),
+            goto: String::from("heavy"),
+        };
+        assert_eq!(expected, Rule::parse(input));
+        assert_eq!(Some(Relation::LessOrEqual), Rule::parse("x<=3:A").relation);
+        assert_eq!(Some(Relation::Equal), Rule::parse("x==3:A").relation);
+    }
+
+    #[test]
+    fn split_by_condition() {
+        let range = Ranges::cube(Interval::new(1, 4000).unwrap(), 4);
+        let condition = Condition {
+            attribute: 2,
+            relation: Relation::Less,
+            value: 2006,
+        };
+        let (matching, rest) = condition.split(&range);
+        assert_eq!(Interval::new(1, 2005), matching.map(|r| r.axes[2]));
+        assert_eq!(
+            vec![range.with_axis(2, Interval::new(2006, 4000).unwrap())],
+            rest
+        );
+
+        let condition = Condition {
+            attribute: 0,
+            relation: Relation::Equal,
+            value: 10,
+        };
+        let (matching, rest) = condition.split(&range);
+        assert_eq!(Interval::new(10, 10), matching.map(|r| r.axes[0]));
+        assert_eq!(2, rest.len());
+
+        let condition = Condition {
+            attribute: 3,
+            relation: Relation::Less,
+            value: 1,
+        };
+        assert_eq!((None, vec![range.clone()]), condition.split(&range));
+    }
+
+    #[test]
+    fn compile_interns_names() {
+        let (workflows, parts) = parse_data(TEST_DATA);
+        let tree = DecisionTree::compile(&workflows, &part_attributes(&parts)).unwrap();
+        assert_eq!(XMAS.map(String::from).to_vec(), tree.attributes);
+        assert_eq!("in", tree.workflow_names[tree.start]);
+        assert_eq!(
+            CompiledRule {
+                condition: Some(Condition {
+                    attribute: 2,
+                    relation: Relation::Less,
+                    value: 2006
+                }),
+                target: Target::Workflow(5),
+            },
+            tree.workflows[0][0]
+        );
+    }
+
+    #[test]
+    fn accepted_boxes_are_disjoint_and_accepted() {
+        let (workflows, _) = parse_data(TEST_DATA);
+        let tree = DecisionTree::compile(&workflows, &XMAS.map(String::from)).unwrap();
+        let domain = Interval::new(1, 4000).unwrap();
+        let boxes = tree.accepted_boxes(domain);
+        let volume: usize = boxes.iter().map(|b| b.volume().unwrap()).sum();
+        assert_eq!(Some(volume), Ranges::total_volume(&boxes));
+        for b in &boxes {
+            let corner = Part {
+                ratings: XMAS
+                    .iter()
+                    .zip(&b.axes)
+                    .map(|(name, interval)| (String::from(*name), interval.to))
+                    .collect(),
+            };
+            assert!(tree.accepts(&corner));
+        }
+    }
+
+    #[test]
+    fn custom_attributes_and_relations() {
+        let input = "in{weight>=15:heavy,color==3:A,R}
+heavy{size<=2:A,R}
+
+{weight=20,size=2}
+{weight=20,size=3}
+{weight=1,color=3}
+{weight=1,color=4}";
+        assert_eq!("26", part1(input));
+    }
+
+    #[test]
+    fn validate_reports_problems() {
+        let input = "in{x<10:a,x<5:R,A}
+a{m>100:b,missing}
+b{a<3:a,R,A}";
+        let (workflows, _) = parse_data(input);
+        let domain = Interval::new(1, 4000).unwrap();
+        let errors = DecisionTree::validate(&workflows, &XMAS.map(String::from), domain);
+        assert_eq!(
+            vec![
+                WorkflowError::DanglingTarget {
+                    workflow: String::from("a"),
+                    target: String::from("missing")
+                },
+                WorkflowError::Cycle(vec![
+                    String::from("a"),
+                    String::from("b"),
+                    String::from("a")
+                ]),
+                WorkflowError::UnreachableRule {
+                    workflow: String::from("in"),
+                    rule: 1
+                },
+                WorkflowError::UnreachableRule {
+                    workflow: String::from("b"),
+                    rule: 2
+                },
+            ],
+            errors
+        );
+        assert!(DecisionTree::compile(&workflows, &[]).is_err());
+
+        let (workflows, _) = parse_data("start{A}");
+        assert_eq!(
+            vec![WorkflowError::MissingStart],
+            DecisionTree::validate(&workflows, &[], domain)
+        );
+
+        let (workflows, _) = parse_data(TEST_DATA);
+        assert_eq!(
+            Vec::<WorkflowError>::new(),
+            DecisionTree::validate(&workflows, &XMAS.map(String::from), domain)
+        );
+    }
+
+    #[test]
+    fn validate_follows_parts_from_start() {
+        let domain = Interval::new(1, 4000).unwrap();
+        // Only parts with x < 10 get to `a`, so its first rule can't match
+        let (workflows, _) = parse_data("in{x<10:a,R}\na{x>100:R,A}");
+        assert_eq!(
+            vec![WorkflowError::UnreachableRule {
+                workflow: String::from("a"),
+                rule: 0
+            }],
+            DecisionTree::validate(&workflows, &XMAS.map(String::from), domain)
+        );
+
+        let (workflows, _) = parse_data("in{A}\norphan{A}");
+        assert_eq!(
+            vec![WorkflowError::UnreachableWorkflow(String::from("orphan"))],
+            DecisionTree::validate(&workflows, &XMAS.map(String::from), domain)
+        );
+
+        // A workflow behind a rule that never matches is unreachable as well
+        let (workflows, _) = parse_data("in{x>5000:a,R}\na{A}");
+        assert_eq!(
+            vec![
+                WorkflowError::UnreachableRule {
+                    workflow: String::from("in"),
+                    rule: 0
+                },
+                WorkflowError::UnreachableWorkflow(String::from("a")),
+            ],
+            DecisionTree::validate(&workflows, &XMAS.map(String::from), domain)
+        );
     }
 
     #[test]
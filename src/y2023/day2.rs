@@ -1,97 +1,124 @@
-use lazy_static::lazy_static;
-use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
 
-lazy_static! {
-    static ref RE_RED: Regex = Regex::new(r" (?<number>\d+) red").unwrap();
-    static ref RE_BLUE: Regex = Regex::new(r" (?<number>\d+) blue").unwrap();
-    static ref RE_GREEN: Regex = Regex::new(r" (?<number>\d+) green").unwrap();
-}
-
-#[derive(Debug, PartialEq)]
-enum Color {
-    Blue,
-    Red,
-    Green,
-}
-
-#[derive(Debug, PartialEq)]
+/// Number of cubes per color. Colors that don't appear count as zero.
+#[derive(Debug, PartialEq, Default, Clone)]
 struct ColoredCubes {
-    red: usize,
-    green: usize,
-    blue: usize,
+    counts: BTreeMap<String, usize>,
 }
 
 impl ColoredCubes {
-    fn power(&self) -> usize {
-        self.green * self.red * self.blue
+    fn count(&self, color: &str) -> usize {
+        self.counts.get(color).copied().unwrap_or_default()
+    }
+
+    /// Product of the counts of `colors`.
+    fn power(&self, colors: &[&str]) -> usize {
+        colors.iter().map(|color| self.count(color)).product()
+    }
+
+    /// Colors with more cubes than `limits` allow, a color without limit allowing none.
+    fn exceeded_colors(&self, limits: &HashMap<String, usize>) -> Vec<String> {
+        self.counts
+            .iter()
+            .filter(|(color, count)| **count > limits.get(*color).copied().unwrap_or_default())
+            .map(|(color, _)| color.clone())
+            .collect()
     }
 }
 
 #[derive(Debug, PartialEq)]
 struct Game {
     id: usize,
-    bag: Vec<ColoredCubes>,
+    rounds: Vec<ColoredCubes>,
+}
+
+#[derive(Debug, PartialEq)]
+struct GameReport {
+    id: usize,
+    /// Fewest cubes of each color that make the game possible.
+    minimal_bag: ColoredCubes,
+    /// Indices of the rounds that need more cubes than the limits allow.
+    impossible_rounds: Vec<usize>,
+    /// Colors whose limit is too low for the game, in alphabetical order.
+    exceeded_colors: Vec<String>,
 }
 
 impl Game {
-    fn is_possible(&self) -> bool {
-        self.bag
+    fn is_possible(&self, limits: &HashMap<String, usize>) -> bool {
+        self.rounds
             .iter()
-            .all(|bag| bag.red <= 12 && bag.green <= 13 && bag.blue <= 14)
+            .all(|round| round.exceeded_colors(limits).is_empty())
     }
 
     fn find_minimal(&self) -> ColoredCubes {
-        let green = self.bag.iter().map(|round| round.green).max().unwrap();
-        let red = self.bag.iter().map(|round| round.red).max().unwrap();
-        let blue = self.bag.iter().map(|round| round.blue).max().unwrap();
-        ColoredCubes { red, green, blue }
+        let mut result = ColoredCubes::default();
+        for (color, count) in self.rounds.iter().flat_map(|round| &round.counts) {
+            let minimal = result.counts.entry(color.clone()).or_default();
+            *minimal = (*minimal).max(*count);
+        }
+        result
+    }
+
+    fn report(&self, limits: &HashMap<String, usize>) -> GameReport {
+        let minimal_bag = self.find_minimal();
+        let exceeded_colors = minimal_bag.exceeded_colors(limits);
+        GameReport {
+            id: self.id,
+            minimal_bag,
+            impossible_rounds: (0..self.rounds.len())
+                .filter(|i| !self.rounds[*i].exceeded_colors(limits).is_empty())
+                .collect(),
+            exceeded_colors,
+        }
     }
 }
 
-fn parse_row(row: &str) -> Game {
-    let mut split_id = row.split(':');
-    let id: usize = split_id
-        .next()
-        .unwrap()
-        .replace("Game ", "")
-        .parse()
-        .unwrap();
-    let split_rounds = split_id.next().unwrap().split(';');
-    let mut bag: Vec<ColoredCubes> = vec![];
-    for x in split_rounds {
-        let green = match RE_GREEN.captures(x) {
-            Some(c) => c.name("number").unwrap().as_str().parse::<usize>().unwrap(),
-            None => 0,
-        };
+const COLORS: [&str; 3] = ["red", "green", "blue"];
 
-        let red = match RE_RED.captures(x) {
-            Some(c) => c.name("number").unwrap().as_str().parse::<usize>().unwrap(),
-            None => 0,
-        };
+fn standard_limits() -> HashMap<String, usize> {
+    COLORS
+        .iter()
+        .zip([12, 13, 14])
+        .map(|(color, limit)| ((*color).to_owned(), limit))
+        .collect()
+}
 
-        let blue = match RE_BLUE.captures(x) {
-            Some(c) => c.name("number").unwrap().as_str().parse::<usize>().unwrap(),
-            None => 0,
-        };
-        bag.push(ColoredCubes { red, green, blue });
+fn parse_round(round: &str) -> ColoredCubes {
+    let mut result = ColoredCubes::default();
+    for cubes in round.split(',') {
+        let (count, color) = cubes.trim().split_once(' ').unwrap();
+        *result.counts.entry(color.to_owned()).or_default() += count.parse::<usize>().unwrap();
+    }
+    result
+}
+
+fn parse_row(row: &str) -> Game {
+    let (id, rounds) = row.split_once(':').unwrap();
+    Game {
+        id: id.replace("Game ", "").parse().unwrap(),
+        rounds: rounds.split(';').map(parse_round).collect(),
     }
-    Game { id, bag }
 }
 
+/// # Panics
+#[must_use]
 pub fn part1(input: &str) -> String {
+    let limits = standard_limits();
     let result: usize = input
         .lines()
         .map(|row| parse_row(row.trim()))
-        .filter(Game::is_possible)
+        .filter(|game| game.is_possible(&limits))
         .map(|g| g.id)
         .sum();
     format!("{result}")
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
     let result: usize = input
         .lines()
-        .map(|row| parse_row(row.trim()).find_minimal().power())
+        .map(|row| parse_row(row.trim()).find_minimal().power(&COLORS))
         .sum();
     format!("{result}")
 }
@@ -107,28 +134,25 @@ mod tests {
         Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
         Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
+    fn cubes(counts: &[(&str, usize)]) -> ColoredCubes {
+        ColoredCubes {
+            counts: counts
+                .iter()
+                .map(|(color, count)| ((*color).to_owned(), *count))
+                .collect(),
+        }
+    }
+
     #[test]
     fn parse_row_test() {
         let input = "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red";
 
         let expected = Game {
             id: 3,
-            bag: vec![
-                ColoredCubes {
-                    green: 8,
-                    blue: 6,
-                    red: 20,
-                },
-                ColoredCubes {
-                    blue: 5,
-                    red: 4,
-                    green: 13,
-                },
-                ColoredCubes {
-                    green: 5,
-                    red: 1,
-                    blue: 0,
-                },
+            rounds: vec![
+                cubes(&[("green", 8), ("blue", 6), ("red", 20)]),
+                cubes(&[("blue", 5), ("red", 4), ("green", 13)]),
+                cubes(&[("green", 5), ("red", 1)]),
             ],
         };
         assert_eq!(expected, parse_row(input));
@@ -143,26 +167,70 @@ mod tests {
     fn find_minimal_cube() {
         let input = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
         let result = parse_row(input).find_minimal();
-        let expected = ColoredCubes {
-            green: 2,
-            blue: 6,
-            red: 4,
-        };
+        let expected = cubes(&[("green", 2), ("blue", 6), ("red", 4)]);
         assert_eq!(expected, result);
     }
 
     #[test]
     fn find_cubes_power() {
-        let cube = ColoredCubes {
-            green: 2,
-            blue: 6,
-            red: 4,
-        };
-        assert_eq!(48, cube.power());
+        let cube = cubes(&[("green", 2), ("blue", 6), ("red", 4)]);
+        assert_eq!(48, cube.power(&COLORS));
+        assert_eq!(0, cube.power(&["red", "yellow"]));
     }
 
     #[test]
     fn test_case_part2() {
         assert_eq!("2286", part2(TEST_DATA));
     }
+
+    #[test]
+    fn reports() {
+        let limits = standard_limits();
+        let game =
+            parse_row("Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red");
+        let expected = GameReport {
+            id: 3,
+            minimal_bag: cubes(&[("green", 13), ("blue", 6), ("red", 20)]),
+            impossible_rounds: vec![0],
+            exceeded_colors: vec!["red".to_owned()],
+        };
+        assert_eq!(expected, game.report(&limits));
+
+        let game =
+            parse_row("Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red");
+        let report = game.report(&limits);
+        assert_eq!(vec![2], report.impossible_rounds);
+        assert_eq!(
+            vec!["blue".to_owned(), "red".to_owned()],
+            report.exceeded_colors
+        );
+
+        let game = parse_row("Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue");
+        assert!(game.report(&limits).impossible_rounds.is_empty());
+    }
+
+    #[test]
+    fn other_colors_and_limits() {
+        // Repeated colors within a round add up
+        let game = parse_row("Game 7: 2 yellow, 1 red; 3 purple; 1 yellow, 1 yellow");
+        assert_eq!(
+            cubes(&[("yellow", 2), ("red", 1), ("purple", 3)]),
+            game.find_minimal()
+        );
+        assert!(!game.is_possible(&standard_limits()));
+
+        let limits = HashMap::from([
+            ("yellow".to_owned(), 2),
+            ("purple".to_owned(), 3),
+            ("red".to_owned(), 1),
+        ]);
+        assert!(game.is_possible(&limits));
+        let limits = HashMap::from([("yellow".to_owned(), 1), ("purple".to_owned(), 5)]);
+        let report = game.report(&limits);
+        assert_eq!(vec![0, 2], report.impossible_rounds);
+        assert_eq!(
+            vec!["red".to_owned(), "yellow".to_owned()],
+            report.exceeded_colors
+        );
+    }
 }
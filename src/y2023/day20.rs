@@ -1,3 +1,4 @@
+use crate::math;
 use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -21,6 +22,7 @@ enum ModuleType {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::struct_field_names)]
 struct Module {
     name: String,
     module_type: ModuleType,
@@ -131,7 +133,7 @@ fn process(name: &str, pulse: &Pulse, mut modules: Vec<Module>) -> (usize, usize
             current_state.handle_input(&input, source);
             for connection in current_state.connections.clone() {
                 queue.push_back((
-                    connection.to_string(),
+                    connection.clone(),
                     current_state.output.as_ref().unwrap().clone(),
                     current_state.name.clone(),
                 ));
@@ -203,7 +205,7 @@ fn parse_line(line: &str, input: &str) -> Module {
         .collect();
 
     Module {
-        name: name.to_string(),
+        name,
         module_type,
         connections,
         output: None,
@@ -272,7 +274,7 @@ fn process_part2(
             current_state.handle_input(&input, source);
             for connection in current_state.connections.clone() {
                 queue.push_back((
-                    connection.to_string(),
+                    connection.clone(),
                     current_state.output.as_ref().unwrap().clone(),
                     current_state.name.clone(),
                 ));
@@ -296,28 +298,6 @@ fn process_part2(
     button_pushes + 1
 }
 
-fn lcm(x: usize, y: usize) -> usize {
-    x * y / gcd(x, y)
-}
-
-fn gcd(mut x: usize, mut y: usize) -> usize {
-    let mut bigger = x;
-    let mut smaller = y;
-    if smaller > bigger {
-        std::mem::swap(&mut x, &mut y);
-    }
-
-    loop {
-        let res = bigger % smaller;
-        if res == 0 {
-            return smaller;
-        }
-
-        bigger = smaller;
-        smaller = res;
-    }
-}
-
 /// # Panics
 #[must_use]
 pub fn part2(input: &str) -> String {
@@ -330,7 +310,7 @@ pub fn part2(input: &str) -> String {
     let cycle3 = process_part2("broadcaster", &Pulse::Low, modules.clone(), "xf");
     let cycle4 = process_part2("broadcaster", &Pulse::Low, modules.clone(), "zl");
 
-    let result = lcm(lcm(cycle1, cycle2), lcm(cycle3, cycle4));
+    let result = math::lcm_of(&[cycle1, cycle2, cycle3, cycle4]);
     format!("{result}")
 }
 
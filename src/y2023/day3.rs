@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 struct Position(isize, isize);
 
 #[derive(Debug, PartialEq)]
@@ -9,26 +9,29 @@ struct Number {
     value: String,
 }
 
+impl Number {
+    fn value(&self) -> usize {
+        self.value.parse().unwrap()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct Board {
     height: usize,
     width: usize,
     numbers: Vec<Number>,
     symbols: HashMap<Position, char>,
+    /// Index into `numbers` of the number covering each cell.
+    cells: HashMap<Position, usize>,
 }
 
 impl Board {
-    fn adjacent_to_symbol(&self, number: &Number) -> bool {
-        let number_positions = Self::get_number_positions(number);
-        for position in number_positions {
-            let neighbors = Self::get_neighbors(&position);
-            for neighbor in neighbors {
-                if self.symbols.contains_key(&neighbor) {
-                    return true;
-                }
-            }
-        }
-        false
+    /// Indices of the numbers touching any symbol, found by walking the symbols once.
+    fn part_numbers(&self) -> BTreeSet<usize> {
+        self.symbols
+            .keys()
+            .flat_map(|position| self.numbers_adjacent_to(position))
+            .collect()
     }
 
     #[allow(clippy::cast_possible_wrap)]
@@ -55,29 +58,57 @@ impl Board {
         neighbors
     }
 
-    fn is_gear(&self, position: &Position, symbol: char) -> bool {
-        if symbol != '*' {
-            return false;
-        }
-        self.numbers
+    /// Indices of the distinct numbers touching the cell, in reading order.
+    fn numbers_adjacent_to(&self, position: &Position) -> Vec<usize> {
+        let mut result: Vec<usize> = Self::get_neighbors(position)
             .iter()
-            .filter(|n| Self::is_number_a_neighbor(position, n))
-            .count()
-            == 2
+            .filter_map(|neighbor| self.cells.get(neighbor).copied())
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
     }
 
-    fn is_number_a_neighbor(position: &Position, number: &Number) -> bool {
-        let neighbors: HashSet<Position> = Self::get_neighbors(position).into_iter().collect();
-        let number_positions: HashSet<Position> =
-            Self::get_number_positions(number).into_iter().collect();
-        neighbors.intersection(&number_positions).next().is_some()
+    /// Values of the numbers next to each kind of symbol. A number counts once per kind, even
+    /// when it touches several symbols of it.
+    fn part_numbers_by_symbol(&self) -> BTreeMap<char, Vec<usize>> {
+        let mut indices: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+        for (position, symbol) in &self.symbols {
+            indices
+                .entry(*symbol)
+                .or_default()
+                .extend(self.numbers_adjacent_to(position));
+        }
+        indices
+            .into_iter()
+            .map(|(symbol, mut numbers)| {
+                numbers.sort_unstable();
+                numbers.dedup();
+                let values = numbers.iter().map(|i| self.numbers[*i].value()).collect();
+                (symbol, values)
+            })
+            .collect()
     }
 
-    fn get_gear_value(&self, position: &Position) -> usize {
-        self.numbers
+    /// Positions of the `symbol`s touching exactly `count` numbers, together with the values of
+    /// those numbers. Gears are the `*`s with two.
+    fn symbols_with_adjacent(&self, symbol: char, count: usize) -> Vec<(Position, Vec<usize>)> {
+        let mut result: Vec<(Position, Vec<usize>)> = self
+            .symbols
             .iter()
-            .filter(|n| Self::is_number_a_neighbor(position, n))
-            .fold(1, |acc, x| acc * x.value.parse::<usize>().unwrap())
+            .filter(|(_, s)| **s == symbol)
+            .map(|(position, _)| {
+                let values: Vec<usize> = self
+                    .numbers_adjacent_to(position)
+                    .iter()
+                    .map(|i| self.numbers[*i].value())
+                    .collect();
+                (position.clone(), values)
+            })
+            .filter(|(_, values)| values.len() == count)
+            .collect();
+        result.sort_unstable();
+        result
     }
 }
 
@@ -135,11 +166,21 @@ fn parse_board(input: &str) -> Board {
         y += 1;
     }
 
+    let cells = numbers
+        .iter()
+        .enumerate()
+        .flat_map(|(i, number)| {
+            Board::get_number_positions(number)
+                .into_iter()
+                .map(move |position| (position, i))
+        })
+        .collect();
     Board {
         height: y.try_into().unwrap(),
         width: input.lines().next().unwrap().len(),
         numbers,
         symbols,
+        cells,
     }
 }
 
@@ -147,16 +188,7 @@ fn parse_board(input: &str) -> Board {
 #[must_use]
 pub fn part1(input: &str) -> String {
     let b = parse_board(input);
-    let mut numbers_to_sum = Vec::new();
-    for number in &b.numbers {
-        if b.adjacent_to_symbol(number) {
-            numbers_to_sum.push(&number.value);
-        }
-    }
-    let result: usize = numbers_to_sum
-        .into_iter()
-        .map(|x| x.parse::<usize>().unwrap())
-        .sum();
+    let result: usize = b.part_numbers().iter().map(|i| b.numbers[*i].value()).sum();
     format!("{result}")
 }
 
@@ -164,12 +196,11 @@ pub fn part1(input: &str) -> String {
 #[must_use]
 pub fn part2(input: &str) -> String {
     let b = parse_board(input);
-    let mut result = 0;
-    for (p, s) in &b.symbols {
-        if b.is_gear(p, *s) {
-            result += b.get_gear_value(p);
-        }
-    }
+    let result: usize = b
+        .symbols_with_adjacent('*', 2)
+        .iter()
+        .map(|(_, values)| values.iter().product::<usize>())
+        .sum();
     format!("{result}")
 }
 
@@ -229,23 +260,15 @@ mod tests {
     }
 
     #[test]
-    fn adjacent_to_symbol_test_adjacent() {
-        let b = parse_board(TEST_DATA);
-        let number = Number {
-            pos: Position(0, 0),
-            value: "467".to_owned(),
-        };
-        assert!(b.adjacent_to_symbol(&number))
-    }
-
-    #[test]
-    fn adjacent_to_symbol_test_not_adjacent() {
+    fn part_numbers_test() {
         let b = parse_board(TEST_DATA);
-        let number = Number {
-            pos: Position(0, 5),
-            value: "114".to_owned(),
-        };
-        assert!(!b.adjacent_to_symbol(&number))
+        let values: Vec<usize> = b
+            .part_numbers()
+            .iter()
+            .map(|i| b.numbers[*i].value())
+            .collect();
+        // 114 and 58 touch no symbol
+        assert_eq!(vec![467, 35, 633, 617, 592, 755, 664, 598], values);
     }
 
     #[test]
@@ -257,4 +280,50 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("467835", part2(TEST_DATA));
     }
+
+    #[test]
+    fn cell_index() {
+        let b = parse_board(TEST_DATA);
+        let index = |position| b.cells.get(&position).map(|i| b.numbers[*i].value());
+        assert_eq!(Some(467), index(Position(0, 2)));
+        assert_eq!(Some(633), index(Position(2, 6)));
+        assert_eq!(None, index(Position(0, 3)));
+        // The star below 467 touches it with two cells but reports it once
+        let adjacent = b.numbers_adjacent_to(&Position(1, 3));
+        let values: Vec<usize> = adjacent.iter().map(|i| b.numbers[*i].value()).collect();
+        assert_eq!(vec![467, 35], values);
+    }
+
+    #[test]
+    fn part_numbers_grouped() {
+        let b = parse_board(TEST_DATA);
+        let expected = BTreeMap::from([
+            ('#', vec![633]),
+            ('$', vec![664]),
+            ('*', vec![467, 35, 617, 755, 598]),
+            ('+', vec![592]),
+        ]);
+        assert_eq!(expected, b.part_numbers_by_symbol());
+    }
+
+    #[test]
+    fn symbols_by_adjacency_count() {
+        let b = parse_board(TEST_DATA);
+        assert_eq!(
+            vec![
+                (Position(1, 3), vec![467, 35]),
+                (Position(8, 5), vec![755, 598])
+            ],
+            b.symbols_with_adjacent('*', 2)
+        );
+        assert_eq!(
+            vec![(Position(4, 3), vec![617])],
+            b.symbols_with_adjacent('*', 1)
+        );
+        assert_eq!(
+            vec![(Position(3, 6), vec![633])],
+            b.symbols_with_adjacent('#', 1)
+        );
+        assert!(b.symbols_with_adjacent('#', 2).is_empty());
+    }
 }
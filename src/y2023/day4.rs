@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use std::fmt::Write;
+
 #[derive(Debug, PartialEq)]
 struct Card {
     id: usize,
@@ -55,7 +58,90 @@ fn parse_row(row: &str) -> Card {
     }
 }
 
-#[must_use] pub fn part1(input: &str) -> String {
+#[derive(Debug, PartialEq)]
+enum CardError {
+    /// Card ids start at 1.
+    InvalidId(usize),
+    Duplicate(usize),
+    /// Card ids have to run from 1 up without gaps.
+    Missing(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct CardRow {
+    id: usize,
+    matches: usize,
+    points: usize,
+    /// Original card included.
+    copies: usize,
+}
+
+/// Outcome of every card once all won copies are scratched, ordered by card id.
+#[derive(Debug, PartialEq)]
+struct Breakdown {
+    rows: Vec<CardRow>,
+}
+
+impl Breakdown {
+    /// Cards may come in any order. Copies past the last card are never won.
+    fn new(mut cards: Vec<Card>) -> Result<Self, CardError> {
+        cards.sort_by_key(|card| card.id);
+        if let Some(card) = cards.first().filter(|card| card.id < 1) {
+            return Err(CardError::InvalidId(card.id));
+        }
+        for (i, card) in cards.iter().enumerate() {
+            match card.id.cmp(&(i + 1)) {
+                Ordering::Less => return Err(CardError::Duplicate(card.id)),
+                Ordering::Greater => return Err(CardError::Missing(i + 1)),
+                Ordering::Equal => {}
+            }
+        }
+
+        // Card i hands its copies to the range of cards after it, so the running number of won
+        // copies only changes where such ranges begin and end
+        let count = cards.len();
+        let mut range_starts = vec![0; count + 1];
+        let mut range_ends = vec![0; count + 1];
+        let mut won = 0;
+        let mut rows = Vec::with_capacity(count);
+        for (i, card) in cards.iter().enumerate() {
+            won = won + range_starts[i] - range_ends[i];
+            let copies = 1 + won;
+            let matches = card.count_winning_numbers();
+            let end = (i + 1 + matches).min(count);
+            if i + 1 < end {
+                range_starts[i + 1] += copies;
+                range_ends[end] += copies;
+            }
+            rows.push(CardRow {
+                id: card.id,
+                matches,
+                points: card.find_score(),
+                copies,
+            });
+        }
+        Ok(Breakdown { rows })
+    }
+
+    fn total_cards(&self) -> usize {
+        self.rows.iter().map(|row| row.copies).sum()
+    }
+
+    fn table(&self) -> String {
+        let mut result = String::from("Card  Matches  Points  Copies");
+        for row in &self.rows {
+            let _ = write!(
+                result,
+                "\n{:>4}  {:>7}  {:>6}  {:>6}",
+                row.id, row.matches, row.points, row.copies
+            );
+        }
+        result
+    }
+}
+
+#[must_use]
+pub fn part1(input: &str) -> String {
     let result = input
         .lines()
         .map(|row| parse_row(row.trim()))
@@ -64,21 +150,11 @@ fn parse_row(row: &str) -> Card {
     format!("{result}")
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
     let cards: Vec<Card> = input.lines().map(|row| parse_row(row.trim())).collect();
-    let mut number_of_cards = vec![1; cards.len()];
-
-    let max = cards.len();
-
-    for (i, card) in cards.into_iter().enumerate() {
-        let winnings = card.count_winning_numbers();
-        for j in 1..=winnings {
-            if i + j <= max {
-                number_of_cards[i + j] += number_of_cards[i];
-            }
-        }
-    }
-    let result: usize = number_of_cards.iter().sum();
+    let result = Breakdown::new(cards).unwrap().total_cards();
     format!("{result}")
 }
 
@@ -128,4 +204,58 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("30", part2(TEST_DATA));
     }
+
+    #[test]
+    fn breakdown_table() {
+        let cards = TEST_DATA.lines().map(|row| parse_row(row.trim())).collect();
+        let breakdown = Breakdown::new(cards).unwrap();
+        let expected = "Card  Matches  Points  Copies
+   1        4       8       1
+   2        2       2       2
+   3        2       2       4
+   4        1       1       8
+   5        0       0      14
+   6        0       0       1";
+        assert_eq!(expected, breakdown.table());
+    }
+
+    #[test]
+    fn card_ids_are_honoured() {
+        // Same cards shuffled: copies follow the ids, not the input order
+        let mut cards: Vec<Card> = TEST_DATA.lines().map(|row| parse_row(row.trim())).collect();
+        cards.reverse();
+        cards.swap(1, 4);
+        let breakdown = Breakdown::new(cards).unwrap();
+        assert_eq!(30, breakdown.total_cards());
+        assert_eq!(
+            vec![1, 2, 3, 4, 5, 6],
+            breakdown.rows.iter().map(|row| row.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn invalid_card_ids() {
+        let cards = |input: &str| input.lines().map(|row| parse_row(row.trim())).collect();
+        assert_eq!(
+            Err(CardError::Duplicate(2)),
+            Breakdown::new(cards("Card 1: 1 | 1\nCard 2: 2 | 2\nCard 2: 3 | 3"))
+        );
+        assert_eq!(
+            Err(CardError::Missing(2)),
+            Breakdown::new(cards("Card 1: 1 | 1\nCard 3: 2 | 2\nCard 4: 3 | 3"))
+        );
+        assert_eq!(
+            Err(CardError::Missing(1)),
+            Breakdown::new(cards("Card 2: 1 | 1\nCard 3: 2 | 2"))
+        );
+        assert_eq!(
+            Err(CardError::InvalidId(0)),
+            Breakdown::new(cards("Card 0: 1 | 1\nCard 1: 2 | 2"))
+        );
+        assert_eq!(
+            Err(CardError::InvalidId(0)),
+            Breakdown::new(cards("Card 0: 1 | 1"))
+        );
+        assert_eq!(Ok(Breakdown { rows: vec![] }), Breakdown::new(vec![]));
+    }
 }
@@ -1,15 +1,4 @@
-#[derive(Debug, PartialEq)]
-struct Range(isize, isize, isize);
-
-impl Range {
-    fn from_str(input: &str) -> Range {
-        let mut split = input.trim().split(' ');
-        let x = split.next().unwrap().parse::<isize>().unwrap();
-        let y = split.next().unwrap().parse::<isize>().unwrap();
-        let z = split.next().unwrap().parse::<isize>().unwrap();
-        Range(x, y, z)
-    }
-}
+use crate::interval::Interval;
 
 fn parse_seeds(input: &str) -> Vec<isize> {
     input
@@ -23,43 +12,9 @@ fn parse_seeds(input: &str) -> Vec<isize> {
         .collect()
 }
 
-fn get_next_value(v: isize, next_part: &str) -> isize {
-    let ranges: Vec<Range> = next_part.lines().skip(1).map(Range::from_str).collect();
-    for range in ranges {
-        if v >= range.1 && v <= range.1 + range.2 {
-            return v + (range.0 - range.1);
-        }
-    }
-    v
-}
-
-/// # Panics
-#[must_use]
-pub fn part1(input: &str) -> String {
-    let seeds = parse_seeds(input);
-    let parts: Vec<&str> = input.split("\n\n").skip(1).collect();
-    let mut seed_final_values: Vec<isize> = Vec::new();
-    for seed in seeds {
-        let mut current_value = seed;
-        for part in &parts {
-            current_value = get_next_value(current_value, part);
-        }
-        seed_final_values.push(current_value);
-    }
-    let result = &seed_final_values.iter().min().unwrap();
-    format!("{result}")
-}
-
 #[derive(Debug, PartialEq, Clone)]
-struct Interval {
-    from: isize,
-    to: isize,
-}
-
-#[derive(Debug, PartialEq)]
 struct MappingInterval {
-    from: isize,
-    to: isize,
+    source: Interval<isize>,
     change: isize,
 }
 
@@ -70,175 +25,195 @@ impl MappingInterval {
         let y = split.next().unwrap().parse::<isize>().unwrap();
         let z = split.next().unwrap().parse::<isize>().unwrap();
         MappingInterval {
-            from: y,
-            to: y + z - 1,
+            source: Interval::new(y, y + z - 1).unwrap(),
             change: x - y,
         }
     }
-}
 
-fn parse_seeds_part2(input: &str) -> Vec<Interval> {
-    let temp = input.lines().next().unwrap().replace("seeds: ", "");
-    let mut numbers = temp.trim().split(' ');
-    let mut result = Vec::new();
-    while let Some(f) = numbers.next() {
-        let from: isize = f.parse().unwrap();
-        let to = from + numbers.next().unwrap().parse::<isize>().unwrap() - 1;
-        result.push(Interval { from, to });
+    fn identity(source: Interval<isize>) -> Self {
+        MappingInterval { source, change: 0 }
     }
-    result
-}
 
-fn parse_mapping_intervals(part: &str) -> Vec<MappingInterval> {
-    part.lines()
-        .skip(1)
-        .map(MappingInterval::from_str)
-        .collect()
+    fn destination(&self) -> Interval<isize> {
+        Interval {
+            from: self.source.from + self.change,
+            to: self.source.to + self.change,
+        }
+    }
 }
 
-fn is_between(x: isize, a: isize, b: isize) -> bool {
-    a <= x && x <= b
+/// Piecewise-linear function on integers. Pieces are sorted, disjoint and cover every integer, so
+/// values outside of the almanac ranges are kept by identity pieces.
+#[derive(Debug, PartialEq, Clone)]
+struct PiecewiseLinearMap {
+    pieces: Vec<MappingInterval>,
 }
 
-fn have_common_elements(i: &Interval, mi: &MappingInterval) -> bool {
-    is_between(i.from, mi.from, mi.to) || is_between(i.to, mi.from, mi.to)
-}
+const EVERYTHING: Interval<isize> = Interval {
+    from: isize::MIN,
+    to: isize::MAX,
+};
 
-fn split_interval(i: &Interval, mi: &MappingInterval) -> (Interval, Vec<Interval>) {
-    // i in mi or equal
-    if is_between(i.from, mi.from, mi.to) && is_between(i.to, mi.from, mi.to) {
-        return (
-            Interval {
-                from: i.from + mi.change,
-                to: i.to + mi.change,
-            },
-            Vec::new(),
-        );
+impl PiecewiseLinearMap {
+    fn identity() -> Self {
+        PiecewiseLinearMap {
+            pieces: vec![MappingInterval::identity(EVERYTHING)],
+        }
     }
-    // mi inside i
-    if is_between(mi.from, i.from, i.to) && is_between(mi.to, i.from, i.to) {
-        if mi.to == i.to {
-            return (
-                Interval {
-                    from: mi.from,
-                    to: mi.to,
-                },
-                vec![Interval {
-                    from: i.from,
-                    to: mi.from - 1,
-                }],
-            );
+
+    /// Builds the map from non-overlapping intervals, in any order.
+    fn from_intervals(intervals: Vec<MappingInterval>) -> Self {
+        let sources: Vec<Interval<isize>> = intervals.iter().map(|i| i.source).collect();
+        let mut uncovered = vec![EVERYTHING];
+        for source in Interval::union(&sources) {
+            uncovered = uncovered
+                .iter()
+                .flat_map(|free| free.subtract(&source))
+                .collect();
         }
-        if mi.from == i.from {
-            return (
-                Interval {
-                    from: mi.from,
-                    to: mi.to,
-                },
-                vec![Interval {
-                    from: mi.to + 1,
-                    to: i.to,
-                }],
-            );
+        let mut pieces = intervals;
+        pieces.extend(uncovered.into_iter().map(MappingInterval::identity));
+        pieces.sort_by_key(|piece| piece.source.from);
+        PiecewiseLinearMap { pieces }.merged()
+    }
+
+    /// Parses one almanac section, including its `x-to-y map:` header.
+    fn from_section(section: &str) -> Self {
+        Self::from_intervals(
+            section
+                .lines()
+                .skip(1)
+                .map(MappingInterval::from_str)
+                .collect(),
+        )
+    }
+
+    /// Joins neighbouring pieces which shift values by the same amount.
+    fn merged(self) -> Self {
+        let mut pieces: Vec<MappingInterval> = Vec::with_capacity(self.pieces.len());
+        for piece in self.pieces {
+            match pieces.last_mut() {
+                Some(last) if last.change == piece.change => last.source.to = piece.source.to,
+                _ => pieces.push(piece),
+            }
         }
-        return (
-            Interval {
-                from: mi.from,
-                to: mi.to,
-            },
-            vec![
-                Interval {
-                    from: i.from,
-                    to: mi.from - 1,
-                },
-                Interval {
-                    from: mi.to + 1,
-                    to: i.to,
-                },
-            ],
-        );
+        PiecewiseLinearMap { pieces }
     }
-    // mi left to i with overlap
-    if mi.from < i.from && is_between(mi.to, i.from, i.to) {
-        return (
-            Interval {
-                from: i.from + mi.change,
-                to: mi.to + mi.change,
-            },
-            vec![Interval {
-                from: mi.to + 1,
-                to: i.to,
-            }],
-        );
+
+    fn piece_index(&self, x: isize) -> usize {
+        self.pieces.partition_point(|piece| piece.source.to < x)
     }
-    // mi right to i with overlap
-    if mi.to > i.to && is_between(mi.from, i.from, i.to) {
-        return (
-            Interval {
-                from: mi.from + mi.change,
-                to: i.to + mi.change,
-            },
-            vec![Interval {
-                from: i.from,
-                to: mi.from - 1,
-            }],
-        );
+
+    fn apply(&self, x: isize) -> isize {
+        x + self.pieces[self.piece_index(x)].change
     }
-    unreachable!()
-}
 
-fn get_next_intervals(
-    intervals: &[Interval],
-    mapping_intervals: &[MappingInterval],
-) -> Vec<Interval> {
-    let mut new_intervals = Vec::new();
-    let mut left_intervals = intervals.to_owned();
-    while !left_intervals.is_empty() {
-        //let i = left_intervals.pop().unwrap();
-        while let Some(i) = left_intervals.pop() {
-            if mapping_intervals
+    /// Function applying `self` first and `next` afterwards.
+    fn then(&self, next: &PiecewiseLinearMap) -> PiecewiseLinearMap {
+        let mut pieces = Vec::new();
+        for piece in &self.pieces {
+            // Split the image of the piece at `next` piece boundaries
+            let image = piece.destination();
+            let first = next.piece_index(image.from);
+            for next_piece in next.pieces[first..]
                 .iter()
-                .any(|x| have_common_elements(&i, x))
+                .take_while(|next_piece| next_piece.source.from <= image.to)
             {
-                let mi = mapping_intervals
-                    .iter()
-                    .find(|x| have_common_elements(&i, x))
-                    .unwrap();
-                let (common, mut left) = split_interval(&i, mi);
-                new_intervals.push(common);
-                left_intervals.append(&mut left);
-                continue;
+                let common = image.intersect(&next_piece.source).unwrap();
+                pieces.push(MappingInterval {
+                    source: Interval {
+                        from: common.from - piece.change,
+                        to: common.to - piece.change,
+                    },
+                    change: piece.change + next_piece.change,
+                });
             }
-            new_intervals.push(i);
         }
+        PiecewiseLinearMap { pieces }.merged()
+    }
+
+    /// Inverse function, `None` when two pieces are mapped onto common values.
+    fn inverse(&self) -> Option<PiecewiseLinearMap> {
+        let mut pieces: Vec<MappingInterval> = self
+            .pieces
+            .iter()
+            .map(|piece| MappingInterval {
+                source: piece.destination(),
+                change: -piece.change,
+            })
+            .collect();
+        pieces.sort_by_key(|piece| piece.source.from);
+        if pieces
+            .windows(2)
+            .any(|w| w[0].source.intersect(&w[1].source).is_some())
+        {
+            return None;
+        }
+        Some(PiecewiseLinearMap { pieces })
+    }
+
+    /// Smallest value the map takes over any of the intervals.
+    fn min_over(&self, intervals: &[Interval<isize>]) -> Option<isize> {
+        intervals
+            .iter()
+            .flat_map(|interval| {
+                self.pieces[self.piece_index(interval.from)..]
+                    .iter()
+                    .map_while(|piece| {
+                        piece
+                            .source
+                            .intersect(interval)
+                            .map(|common| common.from + piece.change)
+                    })
+            })
+            .min()
     }
-    new_intervals
 }
 
-fn get_intervals_minimum(intervals: &[Interval]) -> isize {
-    intervals
-        .iter()
-        .map(|interval| interval.from)
-        .min()
-        .unwrap()
+fn get_next_value(v: isize, next_part: &str) -> isize {
+    PiecewiseLinearMap::from_section(next_part).apply(v)
+}
+
+/// All almanac sections composed into a single seed to location function.
+fn parse_seed_to_location(input: &str) -> PiecewiseLinearMap {
+    input
+        .split("\n\n")
+        .skip(1)
+        .map(PiecewiseLinearMap::from_section)
+        .fold(PiecewiseLinearMap::identity(), |acc, map| acc.then(&map))
 }
 
 /// # Panics
 #[must_use]
-pub fn part2(input: &str) -> String {
-    let seeds = parse_seeds_part2(input);
-    let parts: Vec<&str> = input.split("\n\n").skip(1).collect();
-    let mut minimums: Vec<isize> = Vec::new();
-    for seed in seeds {
-        let mut current_intervals = vec![seed];
-        for part in &parts {
-            let mapping_intervals = parse_mapping_intervals(part);
-            current_intervals = get_next_intervals(&current_intervals, &mapping_intervals);
-        }
-        minimums.push(get_intervals_minimum(&current_intervals));
+pub fn part1(input: &str) -> String {
+    let seed_to_location = parse_seed_to_location(input);
+    let result = parse_seeds(input)
+        .into_iter()
+        .map(|seed| seed_to_location.apply(seed))
+        .min()
+        .unwrap();
+    format!("{result}")
+}
+
+fn parse_seeds_part2(input: &str) -> Vec<Interval<isize>> {
+    let temp = input.lines().next().unwrap().replace("seeds: ", "");
+    let mut numbers = temp.trim().split(' ');
+    let mut result = Vec::new();
+    while let Some(f) = numbers.next() {
+        let from: isize = f.parse().unwrap();
+        let to = from + numbers.next().unwrap().parse::<isize>().unwrap() - 1;
+        result.push(Interval { from, to });
     }
-    let result = &minimums.iter().min().unwrap();
+    result
+}
 
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
+    let seed_to_location = parse_seed_to_location(input);
+    let result = seed_to_location
+        .min_over(&parse_seeds_part2(input))
+        .unwrap();
     format!("{result}")
 }
 
@@ -293,6 +268,7 @@ mod tests {
         52 50 48";
         assert_eq!(81, get_next_value(79, next_part));
         assert_eq!(1, get_next_value(1, next_part));
+        assert_eq!(100, get_next_value(100, next_part));
     }
 
     #[test]
@@ -312,13 +288,88 @@ mod tests {
     fn parsing_mapping_interval() {
         let input = "88 18 7";
         let expected = MappingInterval {
-            from: 18,
-            to: 24,
+            source: Interval { from: 18, to: 24 },
             change: 70,
         };
         assert_eq!(expected, MappingInterval::from_str(input));
     }
 
+    #[test]
+    fn from_section_fills_gaps() {
+        let map = PiecewiseLinearMap::from_section(
+            "seed-to-soil map:
+            50 98 2
+            52 50 48",
+        );
+        let expected = vec![
+            MappingInterval {
+                source: Interval {
+                    from: isize::MIN,
+                    to: 49,
+                },
+                change: 0,
+            },
+            MappingInterval {
+                source: Interval { from: 50, to: 97 },
+                change: 2,
+            },
+            MappingInterval {
+                source: Interval { from: 98, to: 99 },
+                change: -48,
+            },
+            MappingInterval {
+                source: Interval {
+                    from: 100,
+                    to: isize::MAX,
+                },
+                change: 0,
+            },
+        ];
+        assert_eq!(expected, map.pieces);
+    }
+
+    #[test]
+    fn composed_map_matches_step_by_step() {
+        let sections: Vec<PiecewiseLinearMap> = TEST_DATA
+            .split("\n\n")
+            .skip(1)
+            .map(PiecewiseLinearMap::from_section)
+            .collect();
+        let seed_to_location = parse_seed_to_location(TEST_DATA);
+        for seed in 0..120 {
+            let expected = sections.iter().fold(seed, |value, map| map.apply(value));
+            assert_eq!(expected, seed_to_location.apply(seed));
+        }
+        assert_eq!(
+            vec![82, 43, 86, 35],
+            [79, 14, 55, 13].map(|seed| seed_to_location.apply(seed))
+        );
+    }
+
+    #[test]
+    fn inverse_test() {
+        let seed_to_location = parse_seed_to_location(TEST_DATA);
+        let location_to_seed = seed_to_location.inverse().unwrap();
+        for seed in -10..150 {
+            assert_eq!(seed, location_to_seed.apply(seed_to_location.apply(seed)));
+        }
+        assert_eq!(82, location_to_seed.apply(46));
+
+        let not_injective = PiecewiseLinearMap::from_section("map:\n10 0 5");
+        assert_eq!(None, not_injective.inverse());
+    }
+
+    #[test]
+    fn min_over_test() {
+        let seed_to_location = parse_seed_to_location(TEST_DATA);
+        let brute_force = (55..=67).map(|seed| seed_to_location.apply(seed)).min();
+        assert_eq!(
+            brute_force,
+            seed_to_location.min_over(&[Interval { from: 55, to: 67 }])
+        );
+        assert_eq!(None, seed_to_location.min_over(&[]));
+    }
+
     #[test]
     fn test_case_part2() {
         assert_eq!("46", part2(TEST_DATA));
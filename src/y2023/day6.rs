@@ -1,34 +1,52 @@
+use crate::math;
+
 #[derive(Debug, PartialEq)]
 struct TimeDistance {
-    time: usize,
-    distance: usize,
+    time: u128,
+    distance: u128,
 }
 
 impl TimeDistance {
-    fn count_ways_to_win(&self) -> usize {
-        let mut i = 1;
-        while i * (self.time - i) <= self.distance {
-            i += 1;
+    /// Number of whole milliseconds to hold the button that beat the record. Holding `i` goes
+    /// `i * (time - i)`, which peaks at `h = time / 2`. Writing `i = h - k` turns beating the
+    /// record into `k * (k + time % 2) < h * (time - h) - distance`, solved with an integer square
+    /// root. Reaching the record exactly doesn't count.
+    ///
+    /// `None` when the longest distance `h * (time - h)` doesn't fit in `u128`.
+    fn count_ways_to_win(&self) -> Option<u128> {
+        let half = self.time / 2;
+        let odd = self.time % 2;
+        let longest = half.checked_mul(half + odd)?;
+        if longest <= self.distance {
+            return Some(0);
         }
-        self.time - 2 * i + 1
+        // Largest k with k * (k + odd) <= margin
+        let margin = longest - self.distance - 1;
+        let root = math::isqrt(margin);
+        let k = if root * (root + odd) <= margin {
+            root
+        } else {
+            root - 1
+        };
+        Some(2 * k + 1 + odd)
     }
 }
 
 fn parse_data(input: &str) -> Vec<TimeDistance> {
     let mut lines = input.lines();
-    let times: Vec<usize> = lines
+    let times: Vec<u128> = lines
         .next()
         .unwrap()
         .replace("Time:", "")
         .split_whitespace()
-        .map(|x| x.parse::<usize>().unwrap())
+        .map(|x| x.parse::<u128>().unwrap())
         .collect();
-    let distances: Vec<usize> = lines
+    let distances: Vec<u128> = lines
         .next()
         .unwrap()
         .replace("Distance:", "")
         .split_whitespace()
-        .map(|x| x.parse::<usize>().unwrap())
+        .map(|x| x.parse::<u128>().unwrap())
         .collect();
     let mut result = Vec::new();
     for (i, x) in times.iter().enumerate() {
@@ -40,40 +58,43 @@ fn parse_data(input: &str) -> Vec<TimeDistance> {
     result
 }
 
+/// # Panics
+#[must_use]
 pub fn part1(input: &str) -> String {
     let data = parse_data(input);
-    let result: usize = data
+    let result: u128 = data
         .iter()
-        .map(TimeDistance::count_ways_to_win)
-        //.fold(1, |acc, x| acc * x);
+        .map(|race| race.count_ways_to_win().unwrap())
         .product();
     format!("{result}")
 }
 
 fn parse_data_part2(input: &str) -> TimeDistance {
     let mut lines = input.lines();
-    let time: usize = lines
+    let time: u128 = lines
         .next()
         .unwrap()
         .replace("Time:", "")
         .split_whitespace()
         .collect::<String>()
-        .parse::<usize>()
+        .parse::<u128>()
         .unwrap();
-    let distance: usize = lines
+    let distance: u128 = lines
         .next()
         .unwrap()
         .replace("Distance:", "")
         .split_whitespace()
         .collect::<String>()
-        .parse::<usize>()
+        .parse::<u128>()
         .unwrap();
     TimeDistance { time, distance }
 }
 
-#[must_use] pub fn part2(input: &str) -> String {
+/// # Panics
+#[must_use]
+pub fn part2(input: &str) -> String {
     let data = parse_data_part2(input);
-    let result = data.count_ways_to_win();
+    let result = data.count_ways_to_win().unwrap();
     format!("{result}")
 }
 
@@ -113,7 +134,7 @@ mod tests {
     fn parse_data_part2_test() {
         let expected = TimeDistance {
             time: 71530,
-            distance: 940200,
+            distance: 940_200,
         };
         assert_eq!(expected, parse_data_part2(TEST_DATA));
     }
@@ -122,4 +143,59 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("71503", part2(TEST_DATA));
     }
+
+    #[test]
+    fn matches_simulation() {
+        for time in 0..40 {
+            for distance in 0..400 {
+                let expected = (0..=time).filter(|i| i * (time - i) > distance).count();
+                let race = TimeDistance { time, distance };
+                assert_eq!(
+                    Some(u128::try_from(expected).unwrap()),
+                    race.count_ways_to_win(),
+                    "time {time}, distance {distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn record_reached_exactly() {
+        // Holding 10 goes exactly 200, and holding 20 as well
+        let race = TimeDistance {
+            time: 30,
+            distance: 200,
+        };
+        assert_eq!(Some(9), race.count_ways_to_win());
+        let race = TimeDistance {
+            time: 30,
+            distance: 225,
+        };
+        assert_eq!(Some(0), race.count_ways_to_win());
+        let race = TimeDistance {
+            time: 30,
+            distance: 224,
+        };
+        assert_eq!(Some(1), race.count_ways_to_win());
+    }
+
+    #[test]
+    fn large_races() {
+        let race = TimeDistance {
+            time: 1 << 63,
+            distance: 0,
+        };
+        assert_eq!(Some((1 << 63) - 1), race.count_ways_to_win());
+        let half = u128::from(u64::MAX);
+        let race = TimeDistance {
+            time: 2 * half + 1,
+            distance: half * (half + 1) - 1,
+        };
+        assert_eq!(Some(2), race.count_ways_to_win());
+        let race = TimeDistance {
+            time: u128::MAX,
+            distance: 0,
+        };
+        assert_eq!(None, race.count_ways_to_win());
+    }
 }
@@ -1,6 +1,6 @@
 use std::cmp::Ordering;
 
-#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Card {
     A = 14,
     K = 13,
@@ -17,7 +17,7 @@ enum Card {
     Two = 2,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum HandValue {
     FiveOfAKind = 700,
     FourOfAKind = 600,
@@ -28,239 +28,178 @@ enum HandValue {
     HighCard = 100,
 }
 
+/// How cards rank against each other and which of them are wild. A wild card counts as whatever
+/// card makes the hand strongest.
 #[derive(Debug, PartialEq)]
-struct Hand {
-    cards: [Card; 5],
-    value: HandValue,
-    bid: usize,
+struct Rules {
+    /// Rank of every card, indexed by its value.
+    ranks: [usize; 15],
+    wild: &'static [Card],
 }
 
-fn parse_cards(input: &str) -> [Card; 5] {
-    let mut result = [Card::A, Card::A, Card::A, Card::A, Card::A];
-    for (i, c) in input.chars().enumerate() {
-        let card = match c {
-            'A' => Card::A,
-            'K' => Card::K,
-            'Q' => Card::Q,
-            'J' => Card::J,
-            'T' => Card::T,
-            '9' => Card::Nine,
-            '8' => Card::Eight,
-            '7' => Card::Seven,
-            '6' => Card::Six,
-            '5' => Card::Five,
-            '4' => Card::Four,
-            '3' => Card::Three,
-            '2' => Card::Two,
-            _ => unreachable!(),
-        };
-        result[i] = card;
-    }
-    result
-}
-
-fn calculate_value(cards: &[Card]) -> HandValue {
-    let mut number_of_cards = [0; 15];
-    for card in cards {
-        number_of_cards[*card as usize] += 1;
-    }
-
-    if number_of_cards.contains(&5) {
-        return HandValue::FiveOfAKind;
-    }
-
-    if number_of_cards.contains(&4) {
-        return HandValue::FourOfAKind;
-    }
-
-    if number_of_cards.contains(&3) && number_of_cards.contains(&2) {
-        return HandValue::FullHouse;
-    }
-
-    if number_of_cards.contains(&3) {
-        return HandValue::ThreeOfAKind;
-    }
-
-    if number_of_cards.iter().filter(|&&x| x == 2).count() == 2 {
-        return HandValue::TwoPairs;
-    }
-
-    if number_of_cards.contains(&2) {
-        return HandValue::OnePair;
-    }
-    HandValue::HighCard
-}
-
-impl Hand {
-    fn parse_hand(input: &str) -> Hand {
-        let mut split = input.trim().split(' ');
-        let cards = parse_cards(split.next().unwrap());
-        let value = calculate_value(&cards);
-        let bid: usize = split.next().unwrap().parse().unwrap();
-        Hand { cards, value, bid }
-    }
-
-    fn hand_sorter(hand1: &Self, hand2: &Self) -> Ordering {
-        if let Some(o) = hand1.value.partial_cmp(&hand2.value) {
-            if o != Ordering::Equal {
-                return o;
-            }
+const STANDARD: Rules = Rules::new(
+    &[
+        Card::Two,
+        Card::Three,
+        Card::Four,
+        Card::Five,
+        Card::Six,
+        Card::Seven,
+        Card::Eight,
+        Card::Nine,
+        Card::T,
+        Card::J,
+        Card::Q,
+        Card::K,
+        Card::A,
+    ],
+    &[],
+)
+.unwrap();
+
+const JOKERS_WILD: Rules = Rules::new(
+    &[
+        Card::J,
+        Card::Two,
+        Card::Three,
+        Card::Four,
+        Card::Five,
+        Card::Six,
+        Card::Seven,
+        Card::Eight,
+        Card::Nine,
+        Card::T,
+        Card::Q,
+        Card::K,
+        Card::A,
+    ],
+    &[Card::J],
+)
+.unwrap();
+
+/// Number of distinct cards, which every ranking has to order.
+const CARDS: usize = 13;
+
+/// Hands sort by value first and then by the ranks of their cards in order.
+type SortKey = (HandValue, [usize; 5]);
+
+impl Rules {
+    /// `ranking` lists the cards from the weakest. `None` unless it holds every card exactly once.
+    const fn new(ranking: &[Card], wild: &'static [Card]) -> Option<Self> {
+        if ranking.len() != CARDS {
+            return None;
         }
-        for i in 0..5 {
-            if let Some(o) = hand1.cards[i].partial_cmp(&hand2.cards[i]) {
-                if o != Ordering::Equal {
-                    return o;
-                }
+        let mut ranks = [usize::MAX; 15];
+        let mut i = 0;
+        while i < ranking.len() {
+            let card = ranking[i] as usize;
+            if ranks[card] != usize::MAX {
+                return None;
             }
+            ranks[card] = i;
+            i += 1;
         }
-        unreachable!()
+        Some(Rules { ranks, wild })
     }
-}
 
-fn calculate_result(hands: &[Hand]) -> usize {
-    let mut result = 0;
-    for (i, h) in hands.iter().enumerate() {
-        result += (i + 1) * h.bid;
+    fn rank(&self, card: Card) -> usize {
+        self.ranks[card as usize]
     }
-    result
-}
 
-pub fn part1(input: &str) -> String {
-    let mut hands: Vec<Hand> = input.lines().map(Hand::parse_hand).collect();
-    hands.sort_by(Hand::hand_sorter);
-    let result = calculate_result(&hands);
-    format!("{result}")
-}
-
-#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
-enum CardPart2 {
-    A = 14,
-    K = 13,
-    Q = 12,
-    J = 0,
-    T = 10,
-    Nine = 9,
-    Eight = 8,
-    Seven = 7,
-    Six = 6,
-    Five = 5,
-    Four = 4,
-    Three = 3,
-    Two = 2,
+    fn is_wild(&self, card: Card) -> bool {
+        self.wild.contains(&card)
+    }
 }
 
 #[derive(Debug, PartialEq)]
-struct HandPart2 {
-    cards: [CardPart2; 5],
+struct Hand {
+    cards: [Card; 5],
     value: HandValue,
+    /// Ranks of the cards under the rules the hand was parsed with.
+    ranks: [usize; 5],
     bid: usize,
 }
 
-fn parse_cards_part2(input: &str) -> [CardPart2; 5] {
-    let mut result = [
-        CardPart2::A,
-        CardPart2::A,
-        CardPart2::A,
-        CardPart2::A,
-        CardPart2::A,
-    ];
+fn parse_card(c: char) -> Card {
+    match c {
+        'A' => Card::A,
+        'K' => Card::K,
+        'Q' => Card::Q,
+        'J' => Card::J,
+        'T' => Card::T,
+        '9' => Card::Nine,
+        '8' => Card::Eight,
+        '7' => Card::Seven,
+        '6' => Card::Six,
+        '5' => Card::Five,
+        '4' => Card::Four,
+        '3' => Card::Three,
+        '2' => Card::Two,
+        _ => unreachable!(),
+    }
+}
+
+fn parse_cards(input: &str) -> [Card; 5] {
+    let mut result = [Card::A; 5];
     for (i, c) in input.chars().enumerate() {
-        let card = match c {
-            'A' => CardPart2::A,
-            'K' => CardPart2::K,
-            'Q' => CardPart2::Q,
-            'J' => CardPart2::J,
-            'T' => CardPart2::T,
-            '9' => CardPart2::Nine,
-            '8' => CardPart2::Eight,
-            '7' => CardPart2::Seven,
-            '6' => CardPart2::Six,
-            '5' => CardPart2::Five,
-            '4' => CardPart2::Four,
-            '3' => CardPart2::Three,
-            '2' => CardPart2::Two,
-            _ => unreachable!(),
-        };
-        result[i] = card;
+        result[i] = parse_card(c);
     }
     result
 }
 
-fn calculate_value_part2(cards: &[CardPart2]) -> HandValue {
+fn calculate_value(cards: &[Card], rules: &Rules) -> HandValue {
     let mut number_of_cards = [0; 15];
+    let mut wild_cards = 0;
     for card in cards {
-        number_of_cards[*card as usize] += 1;
-    }
-
-    let jokers = &number_of_cards[0];
-
-    if number_of_cards.contains(&5)
-        || (number_of_cards[1..].contains(&4) && *jokers == 1)
-        || (number_of_cards[1..].contains(&3) && *jokers == 2)
-        || (number_of_cards[1..].contains(&2) && *jokers == 3)
-        || *jokers == 4
-    {
-        return HandValue::FiveOfAKind;
-    }
-
-    if number_of_cards.contains(&4)
-        || (number_of_cards[1..].contains(&3) && *jokers == 1)
-        || (number_of_cards[1..].contains(&2) && *jokers == 2)
-        || *jokers == 3
-    {
-        return HandValue::FourOfAKind;
-    }
-
-    if number_of_cards.contains(&3) && number_of_cards.contains(&2)
-        || (number_of_cards[1..].iter().filter(|&&x| x == 2).count() == 2 && *jokers == 1)
-    {
-        return HandValue::FullHouse;
+        if rules.is_wild(*card) {
+            wild_cards += 1;
+        } else {
+            number_of_cards[*card as usize] += 1;
+        }
     }
-
-    if number_of_cards.contains(&3)
-        || (number_of_cards[1..].contains(&2) && *jokers == 1)
-        || *jokers == 2
-    {
-        return HandValue::ThreeOfAKind;
+    // Wild cards are best spent on the most common card
+    let mut counts: Vec<usize> = number_of_cards.into_iter().filter(|x| *x > 0).collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    match counts.first_mut() {
+        Some(most_common) => *most_common += wild_cards,
+        None => counts.push(wild_cards),
     }
 
-    if number_of_cards.iter().filter(|&&x| x == 2).count() == 2 {
-        return HandValue::TwoPairs;
+    match counts.as_slice() {
+        [5, ..] => HandValue::FiveOfAKind,
+        [4, ..] => HandValue::FourOfAKind,
+        [3, 2, ..] => HandValue::FullHouse,
+        [3, ..] => HandValue::ThreeOfAKind,
+        [2, 2, ..] => HandValue::TwoPairs,
+        [2, ..] => HandValue::OnePair,
+        _ => HandValue::HighCard,
     }
-
-    if number_of_cards.contains(&2) || *jokers == 1 {
-        return HandValue::OnePair;
-    }
-    HandValue::HighCard
 }
 
-impl HandPart2 {
-    fn parse_hand(input: &str) -> HandPart2 {
+impl Hand {
+    fn parse_hand(input: &str, rules: &Rules) -> Hand {
         let mut split = input.trim().split(' ');
-        let cards = parse_cards_part2(split.next().unwrap());
-        let value = calculate_value_part2(&cards);
+        let cards = parse_cards(split.next().unwrap());
+        let value = calculate_value(&cards, rules);
         let bid: usize = split.next().unwrap().parse().unwrap();
-        HandPart2 { cards, value, bid }
+        Hand {
+            cards,
+            value,
+            ranks: cards.map(|card| rules.rank(card)),
+            bid,
+        }
+    }
+
+    fn sort_key(&self) -> SortKey {
+        (self.value, self.ranks)
     }
 
     fn hand_sorter(hand1: &Self, hand2: &Self) -> Ordering {
-        if let Some(o) = hand1.value.partial_cmp(&hand2.value) {
-            if o != Ordering::Equal {
-                return o;
-            }
-        }
-        for i in 0..5 {
-            if let Some(o) = hand1.cards[i].partial_cmp(&hand2.cards[i]) {
-                if o != Ordering::Equal {
-                    return o;
-                }
-            }
-        }
-        unreachable!()
+        hand1.sort_key().cmp(&hand2.sort_key())
     }
 }
 
-fn calculate_result_part2(hands: &[HandPart2]) -> usize {
+fn calculate_result(hands: &[Hand]) -> usize {
     let mut result = 0;
     for (i, h) in hands.iter().enumerate() {
         result += (i + 1) * h.bid;
@@ -268,10 +207,24 @@ fn calculate_result_part2(hands: &[HandPart2]) -> usize {
     result
 }
 
+fn total_winnings(input: &str, rules: &Rules) -> usize {
+    let mut hands: Vec<Hand> = input
+        .lines()
+        .map(|line| Hand::parse_hand(line, rules))
+        .collect();
+    hands.sort_by_key(Hand::sort_key);
+    calculate_result(&hands)
+}
+
+#[must_use]
+pub fn part1(input: &str) -> String {
+    let result = total_winnings(input, &STANDARD);
+    format!("{result}")
+}
+
+#[must_use]
 pub fn part2(input: &str) -> String {
-    let mut hands: Vec<HandPart2> = input.lines().map(HandPart2::parse_hand).collect();
-    hands.sort_by(HandPart2::hand_sorter);
-    let result = calculate_result_part2(&hands);
+    let result = total_winnings(input, &JOKERS_WILD);
     format!("{result}")
 }
 
@@ -288,7 +241,8 @@ mod tests {
 
     #[test]
     fn card_comparinson_test() {
-        assert!(Card::A > Card::Three);
+        assert!(STANDARD.rank(Card::A) > STANDARD.rank(Card::Three));
+        assert!(JOKERS_WILD.rank(Card::J) < JOKERS_WILD.rank(Card::Two));
     }
 
     #[test]
@@ -309,47 +263,54 @@ mod tests {
         let expected = Hand {
             cards: [Card::K, Card::T, Card::J, Card::J, Card::T],
             value: HandValue::TwoPairs,
+            ranks: [11, 8, 9, 9, 8],
             bid: 220,
         };
-        assert_eq!(expected, Hand::parse_hand(input))
+        assert_eq!(expected, Hand::parse_hand(input, &STANDARD));
     }
 
     #[test]
     fn calculate_value_test() {
         assert_eq!(
             HandValue::FiveOfAKind,
-            calculate_value(&[Card::A, Card::A, Card::A, Card::A, Card::A])
+            calculate_value(&[Card::A, Card::A, Card::A, Card::A, Card::A], &STANDARD)
         );
         assert_eq!(
             HandValue::FourOfAKind,
-            calculate_value(&[Card::A, Card::A, Card::J, Card::A, Card::A])
+            calculate_value(&[Card::A, Card::A, Card::J, Card::A, Card::A], &STANDARD)
         );
         assert_eq!(
             HandValue::ThreeOfAKind,
-            calculate_value(&[Card::A, Card::Two, Card::J, Card::A, Card::A])
+            calculate_value(&[Card::A, Card::Two, Card::J, Card::A, Card::A], &STANDARD)
         );
         assert_eq!(
             HandValue::FullHouse,
-            calculate_value(&[Card::A, Card::J, Card::J, Card::A, Card::A])
+            calculate_value(&[Card::A, Card::J, Card::J, Card::A, Card::A], &STANDARD)
         );
         assert_eq!(
             HandValue::TwoPairs,
-            calculate_value(&[Card::Two, Card::J, Card::J, Card::A, Card::A])
+            calculate_value(&[Card::Two, Card::J, Card::J, Card::A, Card::A], &STANDARD)
         );
         assert_eq!(
             HandValue::OnePair,
-            calculate_value(&[Card::Two, Card::J, Card::J, Card::Eight, Card::A])
+            calculate_value(
+                &[Card::Two, Card::J, Card::J, Card::Eight, Card::A],
+                &STANDARD
+            )
         );
         assert_eq!(
             HandValue::HighCard,
-            calculate_value(&[Card::Two, Card::J, Card::T, Card::Eight, Card::A])
+            calculate_value(
+                &[Card::Two, Card::J, Card::T, Card::Eight, Card::A],
+                &STANDARD
+            )
         );
     }
 
     #[test]
     fn hand_sorter_test() {
-        let hand1 = Hand::parse_hand("TTKK2 100");
-        let hand2 = Hand::parse_hand("TTKK3 100");
+        let hand1 = Hand::parse_hand("TTKK2 100", &STANDARD);
+        let hand2 = Hand::parse_hand("TTKK3 100", &STANDARD);
         assert_eq!(Ordering::Less, Hand::hand_sorter(&hand1, &hand2));
     }
 
@@ -362,73 +323,43 @@ mod tests {
     fn calculate_value_test_part2() {
         assert_eq!(
             HandValue::FiveOfAKind,
-            calculate_value_part2(&[
-                CardPart2::A,
-                CardPart2::A,
-                CardPart2::A,
-                CardPart2::A,
-                CardPart2::A
-            ])
+            calculate_value(&[Card::A, Card::A, Card::A, Card::A, Card::A], &JOKERS_WILD)
         );
         assert_eq!(
             HandValue::FiveOfAKind,
-            calculate_value_part2(&[
-                CardPart2::A,
-                CardPart2::A,
-                CardPart2::J,
-                CardPart2::A,
-                CardPart2::A
-            ])
+            calculate_value(&[Card::A, Card::A, Card::J, Card::A, Card::A], &JOKERS_WILD)
         );
         assert_eq!(
             HandValue::FourOfAKind,
-            calculate_value_part2(&[
-                CardPart2::A,
-                CardPart2::Two,
-                CardPart2::J,
-                CardPart2::A,
-                CardPart2::A
-            ])
+            calculate_value(
+                &[Card::A, Card::Two, Card::J, Card::A, Card::A],
+                &JOKERS_WILD
+            )
         );
         assert_eq!(
             HandValue::FiveOfAKind,
-            calculate_value_part2(&[
-                CardPart2::A,
-                CardPart2::J,
-                CardPart2::J,
-                CardPart2::A,
-                CardPart2::A
-            ])
+            calculate_value(&[Card::A, Card::J, Card::J, Card::A, Card::A], &JOKERS_WILD)
         );
         assert_eq!(
             HandValue::FourOfAKind,
-            calculate_value_part2(&[
-                CardPart2::Two,
-                CardPart2::J,
-                CardPart2::J,
-                CardPart2::A,
-                CardPart2::A
-            ])
+            calculate_value(
+                &[Card::Two, Card::J, Card::J, Card::A, Card::A],
+                &JOKERS_WILD
+            )
         );
         assert_eq!(
             HandValue::ThreeOfAKind,
-            calculate_value_part2(&[
-                CardPart2::Two,
-                CardPart2::J,
-                CardPart2::J,
-                CardPart2::Eight,
-                CardPart2::A
-            ])
+            calculate_value(
+                &[Card::Two, Card::J, Card::J, Card::Eight, Card::A],
+                &JOKERS_WILD
+            )
         );
         assert_eq!(
             HandValue::OnePair,
-            calculate_value_part2(&[
-                CardPart2::Two,
-                CardPart2::J,
-                CardPart2::T,
-                CardPart2::Eight,
-                CardPart2::A
-            ])
+            calculate_value(
+                &[Card::Two, Card::J, Card::T, Card::Eight, Card::A],
+                &JOKERS_WILD
+            )
         );
     }
 
@@ -436,4 +367,84 @@ mod tests {
     fn test_case_part2() {
         assert_eq!("5905", part2(TEST_DATA));
     }
+
+    #[test]
+    fn jokers_sort_below_twos() {
+        let hand1 = Hand::parse_hand("JKKK2 1", &JOKERS_WILD);
+        let hand2 = Hand::parse_hand("QQQQ2 1", &JOKERS_WILD);
+        assert_eq!(HandValue::FourOfAKind, hand1.value);
+        assert_eq!(Ordering::Less, Hand::hand_sorter(&hand1, &hand2));
+        assert_eq!(
+            HandValue::FiveOfAKind,
+            calculate_value(&parse_cards("JJJJJ"), &JOKERS_WILD)
+        );
+    }
+
+    #[test]
+    fn other_rule_sets() {
+        const JOKERS_AND_TWOS_WILD: Rules = Rules {
+            ranks: JOKERS_WILD.ranks,
+            wild: &[Card::J, Card::Two],
+        };
+        // Aces rank below twos instead of above kings
+        const ACES_LOW: Rules = Rules::new(
+            &[
+                Card::A,
+                Card::Two,
+                Card::Three,
+                Card::Four,
+                Card::Five,
+                Card::Six,
+                Card::Seven,
+                Card::Eight,
+                Card::Nine,
+                Card::T,
+                Card::J,
+                Card::Q,
+                Card::K,
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            HandValue::FiveOfAKind,
+            calculate_value(&parse_cards("2J2KK"), &JOKERS_AND_TWOS_WILD)
+        );
+        assert_eq!(
+            HandValue::FullHouse,
+            calculate_value(&parse_cards("2QQKK"), &JOKERS_AND_TWOS_WILD)
+        );
+
+        let hand1 = Hand::parse_hand("A2345 1", &ACES_LOW);
+        let hand2 = Hand::parse_hand("23456 1", &ACES_LOW);
+        assert_eq!(Ordering::Less, Hand::hand_sorter(&hand1, &hand2));
+        let hand1 = Hand::parse_hand("A2345 1", &STANDARD);
+        let hand2 = Hand::parse_hand("23456 1", &STANDARD);
+        assert_eq!(Ordering::Greater, Hand::hand_sorter(&hand1, &hand2));
+    }
+
+    #[test]
+    fn rankings_are_validated() {
+        let missing_ace = [
+            Card::Two,
+            Card::Three,
+            Card::Four,
+            Card::Five,
+            Card::Six,
+            Card::Seven,
+            Card::Eight,
+            Card::Nine,
+            Card::T,
+            Card::J,
+            Card::Q,
+            Card::K,
+        ];
+        assert_eq!(None, Rules::new(&missing_ace, &[]));
+        let mut twice_king = missing_ace.to_vec();
+        twice_king.push(Card::K);
+        assert_eq!(None, Rules::new(&twice_king, &[]));
+        let mut complete = missing_ace.to_vec();
+        complete.push(Card::A);
+        assert_eq!(Some(STANDARD), Rules::new(&complete, &[]));
+    }
 }
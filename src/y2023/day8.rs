@@ -1,3 +1,4 @@
+use crate::math;
 use std::collections::HashMap;
 
 #[derive(Debug, PartialEq)]
@@ -9,12 +10,8 @@ struct Choice {
 impl Choice {
     fn make_choice(&self, c: char) -> String {
         match c {
-            'L' => {
-                return String::from(self.left.as_str());
-            }
-            'R' => {
-                return String::from(self.right.as_str());
-            }
+            'L' => String::from(self.left.as_str()),
+            'R' => String::from(self.right.as_str()),
             _ => unreachable!(),
         }
     }
@@ -76,25 +73,15 @@ pub fn part1(input: &str) -> String {
     format!("{result}")
 }
 
-fn vector_lcm(arguments: Vec<usize>) -> usize {
-    arguments
-        .into_iter()
-        .fold(1, |acc, x| num::integer::lcm(x, acc))
-}
-
 /// # Panics
 #[must_use]
 pub fn part2(input: &str) -> String {
     let network = parse_data(input);
     let ending_with_a: Vec<String> = network
         .elements
-        .iter()
-        .filter_map(|(k, _v)| {
-            if k.ends_with('A') {
-                return Some(String::from(k));
-            };
-            None
-        })
+        .keys()
+        .filter(|k| k.ends_with('A'))
+        .map(String::from)
         .collect();
 
     let mut cycles = HashMap::new();
@@ -126,7 +113,7 @@ pub fn part2(input: &str) -> String {
     // cycles smaller than 2 * {smallest cycle}, calculate lcm from all such possible cycle lengths
     // and then return the smallest.
     let cycle_lengths: Vec<usize> = cycles.into_values().collect();
-    let result = vector_lcm(cycle_lengths);
+    let result = math::lcm_of(&cycle_lengths);
     format!("{result}")
 }
 
@@ -1,66 +1,116 @@
-fn is_list_constant(numbers: &[isize]) -> bool {
+use num::BigInt;
+
+fn is_list_constant<T: PartialEq>(numbers: &[T]) -> bool {
     let first_value = numbers.first().unwrap();
     numbers.iter().skip(1).all(|x| x == first_value)
 }
 
-fn process_number_list(numbers: &[isize], forward: bool) -> isize {
-    let mut lists: Vec<Vec<isize>> = Vec::new();
-    let mut current_list = numbers.to_vec().clone();
-    lists.push((numbers).to_vec());
-
-    while !is_list_constant(&current_list) {
-        let new_vec: Vec<isize> = current_list
-            .windows(2)
-            .map(|window| window.get(1).unwrap() - window.first().unwrap())
-            .collect();
-        lists.push(new_vec.clone());
-        current_list = new_vec;
+#[derive(Debug, PartialEq)]
+enum ExtrapolationError {
+    Empty,
+    /// The differences don't settle on a constant before running out of values, so nothing
+    /// confirms a polynomial.
+    NotPolynomial,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Polynomial through a sequence, stored as Newton forward differences at its first value.
+#[derive(Debug, PartialEq)]
+struct Extrapolation {
+    /// First values of the difference layers, down to the constant one.
+    leading: Vec<BigInt>,
+    len: usize,
+}
+
+impl Extrapolation {
+    /// Needs the constant layer of differences to hold at least two values.
+    fn fit(values: &[i64]) -> Result<Self, ExtrapolationError> {
+        if values.is_empty() {
+            return Err(ExtrapolationError::Empty);
+        }
+        let mut layer: Vec<BigInt> = values.iter().map(|v| BigInt::from(*v)).collect();
+        let mut leading = Vec::new();
+        while !is_list_constant(&layer) {
+            leading.push(layer[0].clone());
+            layer = layer
+                .windows(2)
+                .map(|window| &window[1] - &window[0])
+                .collect();
+        }
+        if layer.len() < 2 {
+            return Err(ExtrapolationError::NotPolynomial);
+        }
+        leading.push(layer[0].clone());
+        Ok(Extrapolation {
+            leading,
+            len: values.len(),
+        })
     }
 
-    let mut new_value = 0;
-    for list in lists.iter().rev() {
-        if forward {
-            new_value += list.last().unwrap();
-        } else {
-            new_value = list.first().unwrap() - new_value;
+    fn degree(&self) -> usize {
+        self.leading.len() - 1
+    }
+
+    /// Value at `index`, counted from the first value of the sequence, by
+    /// `sum(binomial(index, j) * leading[j])`. Negative indices lie before the sequence.
+    fn value_at(&self, index: &BigInt) -> BigInt {
+        let mut binomial = BigInt::from(1);
+        let mut result = BigInt::from(0);
+        for (j, difference) in self.leading.iter().enumerate() {
+            if j > 0 {
+                // Exact, since the product is j times the next binomial coefficient
+                binomial = binomial * (index - j + 1) / j;
+            }
+            result += &binomial * difference;
         }
+        result
     }
 
-    new_value
+    /// The `k`-th value after the last one, or before the first one, where `k = 0` is the end
+    /// value itself.
+    fn extrapolate(&self, direction: Direction, k: u64) -> BigInt {
+        let index = match direction {
+            Direction::Forward => BigInt::from(self.len - 1) + k,
+            Direction::Backward => -BigInt::from(k),
+        };
+        self.value_at(&index)
+    }
 }
-/// # Panics
-#[must_use]
-pub fn part1(input: &str) -> String {
-    let number_lines: Vec<Vec<isize>> = input
+
+fn parse_lines(input: &str) -> Vec<Vec<i64>> {
+    input
         .lines()
         .map(|line| {
             line.split_whitespace()
-                .map(|number| number.parse::<isize>().unwrap())
-                .collect::<Vec<isize>>()
+                .map(|number| number.parse::<i64>().unwrap())
+                .collect()
         })
-        .collect();
-    let result: isize = number_lines
+        .collect()
+}
+
+fn sum_of_next_values(input: &str, direction: Direction) -> BigInt {
+    parse_lines(input)
         .iter()
-        .map(|line| process_number_list(line, true))
-        .sum();
+        .map(|line| Extrapolation::fit(line).unwrap().extrapolate(direction, 1))
+        .sum()
+}
+
+/// # Panics
+#[must_use]
+pub fn part1(input: &str) -> String {
+    let result = sum_of_next_values(input, Direction::Forward);
     format!("{result}")
 }
 
 /// # Panics
 #[must_use]
 pub fn part2(input: &str) -> String {
-    let number_lines: Vec<Vec<isize>> = input
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|number| number.parse::<isize>().unwrap())
-                .collect::<Vec<isize>>()
-        })
-        .collect();
-    let result: isize = number_lines
-        .iter()
-        .map(|line| process_number_list(line, false))
-        .sum();
+    let result = sum_of_next_values(input, Direction::Backward);
     format!("{result}")
 }
 
@@ -73,6 +123,12 @@ mod tests {
         1 3 6 10 15 21
         10 13 16 21 30 45";
 
+    fn extrapolate(values: &[i64], direction: Direction, k: u64) -> BigInt {
+        Extrapolation::fit(values)
+            .unwrap()
+            .extrapolate(direction, k)
+    }
+
     #[test]
     fn test_case_part1() {
         assert_eq!("114", part1(TEST_DATA));
@@ -80,6 +136,76 @@ mod tests {
 
     #[test]
     fn test_case_part2() {
-        assert_eq!(5, process_number_list(&vec![10, 13, 16, 21, 30, 45], false));
+        assert_eq!(
+            BigInt::from(5),
+            extrapolate(&[10, 13, 16, 21, 30, 45], Direction::Backward, 1)
+        );
+        assert_eq!("2", part2(TEST_DATA));
+    }
+
+    #[test]
+    fn degrees() {
+        let degree = |values: &[i64]| Extrapolation::fit(values).unwrap().degree();
+        assert_eq!(1, degree(&[0, 3, 6, 9, 12, 15]));
+        assert_eq!(2, degree(&[1, 3, 6, 10, 15, 21]));
+        assert_eq!(3, degree(&[10, 13, 16, 21, 30, 45]));
+        assert_eq!(0, degree(&[7, 7]));
+        assert_eq!(0, degree(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn further_steps() {
+        let triangular = [1, 3, 6, 10, 15, 21];
+        assert_eq!(
+            BigInt::from(21),
+            extrapolate(&triangular, Direction::Forward, 0)
+        );
+        assert_eq!(
+            BigInt::from(1),
+            extrapolate(&triangular, Direction::Backward, 0)
+        );
+        // The n-th triangular number is n(n+1)/2, with the sequence starting at n = 1
+        assert_eq!(
+            BigInt::from(5050),
+            extrapolate(&triangular, Direction::Forward, 94)
+        );
+        assert_eq!(
+            BigInt::from(0),
+            extrapolate(&triangular, Direction::Backward, 1)
+        );
+        assert_eq!(
+            BigInt::from(36),
+            extrapolate(&triangular, Direction::Backward, 10)
+        );
+
+        // Cubes far beyond the range of i64
+        let cubes = [0, 1, 8, 27, 64];
+        let expected: BigInt = "1000000000000000000000000000000".parse().unwrap();
+        assert_eq!(
+            expected,
+            extrapolate(&cubes, Direction::Forward, 10_000_000_000 - 4)
+        );
+        assert_eq!(
+            -expected,
+            extrapolate(&cubes, Direction::Backward, 10_000_000_000)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(ExtrapolationError::Empty), Extrapolation::fit(&[]));
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            Extrapolation::fit(&[5])
+        );
+        // Powers of two never reach a constant layer
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            Extrapolation::fit(&[1, 2, 4, 8, 16, 32])
+        );
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            Extrapolation::fit(&[1, 3, 6, 10, 15, 22])
+        );
     }
 }